@@ -1,15 +1,33 @@
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use futures::Future;
+use mcp_core::prompt::Prompt;
 use mcp_core::protocol::{
-    CallToolResult, GetPromptResult, Implementation, InitializeResult, JsonRpcError,
-    JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, ListPromptsResult,
-    ListResourcesResult, ListToolsResult, ReadResourceResult, ServerCapabilities, METHOD_NOT_FOUND,
+    ArgumentInfo, CallToolResult, CancelledNotification, CompleteRequest, CompleteResult,
+    EmptyResult, GetPromptResult, Implementation, InitializeResult, JsonRpcError, JsonRpcMessage,
+    JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, ListChanged, ListPromptsResult,
+    ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, LoggingLevel, ProgressToken,
+    ReadResourceResult, Reference, RequestId, ResourceUpdatedNotification, RootsCapability,
+    RootsListChangedNotification, ServerCapabilities, SetLevelRequest, SubscribeRequest,
+    UnsubscribeRequest, METHOD_NOT_FOUND,
 };
-use serde::{Deserialize, Serialize};
+use mcp_core::{Meta, ProtocolVersion, Resource, ResourceTemplate, Tool};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::Mutex;
 use tower::{Service, ServiceExt}; // for Service::ready()
 
+use crate::dispatch::{Dispatcher, ProgressCallback, Responder};
+use crate::elicitation::ElicitationHandler;
+use crate::roots::RootsProvider;
+use crate::sampling::SamplingHandler;
+use crate::transport::ServerMessages;
+
+pub use mcp_core::protocol::ClientCapabilities;
+
 pub type BoxError = Box<dyn std::error::Error + Sync + Send>;
 
 /// Error type for MCP client operations.
@@ -30,6 +48,18 @@ pub enum Error {
     #[error("Not initialized")]
     NotInitialized,
 
+    #[error("Tool call failed: {0}")]
+    ToolFailed(String),
+
+    #[error("Server chose protocol version {0}, which this client does not support")]
+    UnsupportedProtocolVersion(String),
+
+    #[error("Protocol version {version} does not support {feature}")]
+    NotSupportedByVersion {
+        version: ProtocolVersion,
+        feature: &'static str,
+    },
+
     #[error("Timeout or service not ready")]
     NotReady,
 
@@ -61,11 +91,6 @@ pub struct ClientInfo {
     pub version: String,
 }
 
-#[derive(Serialize, Deserialize, Default)]
-pub struct ClientCapabilities {
-    // Add fields as needed. For now, empty capabilities are fine.
-}
-
 #[derive(Serialize, Deserialize)]
 pub struct InitializeParams {
     #[serde(rename = "protocolVersion")]
@@ -90,13 +115,64 @@ pub trait McpClientTrait: Send + Sync {
 
     async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult, Error>;
 
+    async fn list_resource_templates(
+        &self,
+        next_cursor: Option<String>,
+    ) -> Result<ListResourceTemplatesResult, Error>;
+
+    /// Ask the server to notify the client whenever the resource at `uri` changes. The
+    /// notifications arrive on [`McpClientTrait::resource_updates`].
+    async fn subscribe_resource(&self, uri: &str) -> Result<(), Error>;
+
+    async fn unsubscribe_resource(&self, uri: &str) -> Result<(), Error>;
+
+    /// A stream of the updates to subscribed resources that arrive from now on
+    fn resource_updates(&self) -> BoxStream<'static, ResourceUpdatedNotification>;
+
+    /// A stream of the lists the server announces changes to from now on, such as its
+    /// tools. Fetch the list again to pick up the change.
+    fn list_changed(&self) -> BoxStream<'static, ListChanged>;
+
     async fn list_tools(&self, next_cursor: Option<String>) -> Result<ListToolsResult, Error>;
 
     async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult, Error>;
 
+    /// Call a tool with `meta` attached to the request under `_meta`, e.g. to propagate
+    /// trace context. The `_meta` of the result is available on the result.
+    async fn call_tool_with_meta(
+        &self,
+        name: &str,
+        arguments: Value,
+        meta: Meta,
+    ) -> Result<CallToolResult, Error>;
+
+    /// Call a tool, asking the server to report its progress to `on_progress` while it runs
+    async fn call_tool_with_progress(
+        &self,
+        name: &str,
+        arguments: Value,
+        on_progress: ProgressCallback,
+    ) -> Result<CallToolResult, Error>;
+
     async fn list_prompts(&self, next_cursor: Option<String>) -> Result<ListPromptsResult, Error>;
 
     async fn get_prompt(&self, name: &str, arguments: Value) -> Result<GetPromptResult, Error>;
+
+    /// Ask the server for values of the argument `argument` of a prompt or resource
+    /// template that complete `value`, what the user typed so far
+    async fn complete(
+        &self,
+        reference: Reference,
+        argument: &str,
+        value: &str,
+    ) -> Result<CompleteResult, Error>;
+
+    /// Ask the server to send log messages at `level` and above. They are emitted as
+    /// `tracing` events tagged with the server name.
+    async fn set_log_level(&self, level: LoggingLevel) -> Result<(), Error>;
+
+    /// Tell the server that the roots returned by the roots provider changed
+    async fn notify_roots_list_changed(&self) -> Result<(), Error>;
 }
 
 /// The MCP client is the interface for MCP operations.
@@ -110,6 +186,8 @@ where
     next_id: AtomicU64,
     server_capabilities: Option<ServerCapabilities>,
     server_info: Option<Implementation>,
+    protocol_version: Option<ProtocolVersion>,
+    dispatcher: Dispatcher,
 }
 
 impl<S> McpClient<S>
@@ -124,7 +202,115 @@ where
             next_id: AtomicU64::new(1),
             server_capabilities: None,
             server_info: None,
+            protocol_version: None,
+            dispatcher: Dispatcher::default(),
+        }
+    }
+
+    /// Handle the requests and notifications the server sends on its own, such as progress
+    /// updates. Take them from the transport handle before wrapping it in a service:
+    ///
+    /// ```ignore
+    /// let handle = transport.start().await?;
+    /// let server_messages = handle.take_server_messages();
+    /// let mut client = McpClient::new(McpService::new(handle));
+    /// if let Some(messages) = server_messages {
+    ///     client = client.with_server_messages(messages);
+    /// }
+    /// ```
+    pub fn with_server_messages(mut self, messages: ServerMessages) -> Self {
+        let service = self.service.get_mut().clone();
+        let respond: Responder = Arc::new(move |response| {
+            let mut service = service.clone();
+            Box::pin(async move {
+                if service.ready().await.is_err() || service.call(response).await.is_err() {
+                    tracing::debug!("Failed to send a response to the server");
+                }
+            })
+        });
+        self.dispatcher.spawn(messages, respond);
+        self
+    }
+
+    /// Answer the server's `elicitation/create` requests with `handler`. The client
+    /// announces the elicitation capability when it initializes, so set the handler first.
+    pub fn with_elicitation_handler(self, handler: impl ElicitationHandler + 'static) -> Self {
+        self.dispatcher.set_elicitation_handler(Arc::new(handler));
+        self
+    }
+
+    /// Answer the server's `roots/list` requests with `provider`. The client announces the
+    /// roots capability when it initializes, so set the provider first.
+    pub fn with_roots_provider(self, provider: impl RootsProvider + 'static) -> Self {
+        self.dispatcher.set_roots_provider(Arc::new(provider));
+        self
+    }
+
+    /// Answer the server's `sampling/createMessage` requests with `handler`. The client
+    /// announces the sampling capability when it initializes, so set the handler first.
+    pub fn with_sampling_handler(self, handler: impl SamplingHandler + 'static) -> Self {
+        self.dispatcher.set_sampling_handler(Arc::new(handler));
+        self
+    }
+
+    /// Call a tool that returns structured output and deserialize the output into `T`,
+    /// which should match the tool's output schema
+    pub async fn call_tool_as<T>(&self, name: &str, arguments: Value) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let result = self.call_tool(name, arguments).await?;
+        if result.is_error == Some(true) {
+            let message = result
+                .content
+                .iter()
+                .filter_map(|content| content.as_text())
+                .collect::<Vec<_>>()
+                .join("\n");
+            return Err(Error::ToolFailed(message));
         }
+        let structured = result.structured_content.ok_or_else(|| {
+            Error::UnexpectedResponse(format!("tool '{}' returned no structured content", name))
+        })?;
+        Ok(serde_json::from_value(structured)?)
+    }
+
+    /// Stream every tool of the server, fetching the pages of the list as they are
+    /// consumed
+    pub fn list_all_tools(&self) -> BoxStream<'_, Result<Tool, Error>> {
+        paginate(move |cursor| async move {
+            let page = self.list_tools(cursor).await?;
+            Ok((page.tools, page.next_cursor))
+        })
+    }
+
+    /// Stream every resource of the server, see [`McpClient::list_all_tools`]
+    pub fn list_all_resources(&self) -> BoxStream<'_, Result<Resource, Error>> {
+        paginate(move |cursor| async move {
+            let page = self.list_resources(cursor).await?;
+            Ok((page.resources, page.next_cursor))
+        })
+    }
+
+    /// Stream every resource template of the server, see [`McpClient::list_all_tools`]
+    pub fn list_all_resource_templates(&self) -> BoxStream<'_, Result<ResourceTemplate, Error>> {
+        paginate(move |cursor| async move {
+            let page = self.list_resource_templates(cursor).await?;
+            Ok((page.resource_templates, page.next_cursor))
+        })
+    }
+
+    /// Stream every prompt of the server, see [`McpClient::list_all_tools`]
+    pub fn list_all_prompts(&self) -> BoxStream<'_, Result<Prompt, Error>> {
+        paginate(move |cursor| async move {
+            let page = self.list_prompts(cursor).await?;
+            Ok((page.prompts, page.next_cursor))
+        })
+    }
+
+    /// The protocol version agreed on with the server, None until initialized
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        self.protocol_version
     }
 
     /// Send a JSON-RPC request and check we don't get an error response.
@@ -135,16 +321,27 @@ where
         let mut service = self.service.lock().await;
         service.ready().await.map_err(|_| Error::NotReady)?;
 
-        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let id = RequestId::Number(self.next_id.fetch_add(1, Ordering::SeqCst).into());
         let request = JsonRpcMessage::Request(JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
-            id: Some(id),
+            id: Some(id.clone()),
             method: method.to_string(),
             params: Some(params.clone()),
         });
 
-        let response_msg = service
-            .call(request)
+        // If the call times out or is dropped the server is told to stop working on it,
+        // except for `initialize` which the protocol forbids cancelling
+        let cancellable = if method == "initialize" {
+            vec![]
+        } else {
+            vec![id.clone()]
+        };
+        let mut cancel = CancelOnDrop::new(service.clone(), cancellable);
+        // The service is only needed to send the request, so other requests can go out
+        // while this one waits for its response
+        let call = service.call(request);
+        drop(service);
+        let response_msg = call
             .await
             .map_err(|e| Error::McpServerError {
                 server: self
@@ -155,14 +352,125 @@ where
                 method: method.to_string(),
                 // we don't need include params because it can be really large
                 source: Box::new(e.into()),
-            })?;
+            })
+            .inspect_err(|e| cancel.set_reason(e))?;
+        cancel.disarm();
+
+        Self::parse_response(&id, response_msg)
+    }
+
+    /// Send several JSON-RPC requests as one batch, saving a round trip per request.
+    ///
+    /// The outer error covers the batch as a whole, while each inner result is the outcome
+    /// of the request at the same position in `requests`. Only protocol version 2025-03-26
+    /// has batches, with any other version the requests are sent one by one, concurrently,
+    /// and their results returned the same way. Batches time out like single requests, with
+    /// the timeout of the client's service.
+    pub async fn send_batch<R>(
+        &self,
+        requests: Vec<(&str, Value)>,
+    ) -> Result<Vec<Result<R, Error>>, Error>
+    where
+        R: for<'de> Deserialize<'de>,
+    {
+        if self
+            .protocol_version
+            .is_some_and(|version| !version.supports_batching())
+        {
+            let calls = requests
+                .into_iter()
+                .map(|(method, params)| self.send_request(method, params));
+            return Ok(futures::future::join_all(calls).await);
+        }
+
+        let mut service = self.service.lock().await;
+        service.ready().await.map_err(|_| Error::NotReady)?;
+
+        let mut ids = Vec::with_capacity(requests.len());
+        let mut messages = Vec::with_capacity(requests.len());
+        for (method, params) in requests {
+            let id = RequestId::Number(self.next_id.fetch_add(1, Ordering::SeqCst).into());
+            messages.push(JsonRpcMessage::Request(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(id.clone()),
+                method: method.to_string(),
+                params: Some(params),
+            }));
+            ids.push(id);
+        }
 
+        let mut cancel = CancelOnDrop::new(service.clone(), ids.clone());
+        let call = service.call(JsonRpcMessage::Batch(messages));
+        drop(service);
+        let response_msg = call
+            .await
+            .map_err(|e| Error::McpServerError {
+                server: self
+                    .server_info
+                    .as_ref()
+                    .map(|s| s.name.clone())
+                    .unwrap_or("".to_string()),
+                method: "batch".to_string(),
+                source: Box::new(e.into()),
+            })
+            .inspect_err(|e| cancel.set_reason(e))?;
+        cancel.disarm();
+
+        let responses = match response_msg {
+            JsonRpcMessage::Batch(responses) => responses,
+            // The server rejected the batch as a whole
+            JsonRpcMessage::Error(JsonRpcError { error, .. }) => {
+                return Err(Error::RpcError {
+                    code: error.code,
+                    message: error.message,
+                });
+            }
+            _ => {
+                return Err(Error::UnexpectedResponse(
+                    "expected a batch response".to_string(),
+                ))
+            }
+        };
+
+        // Responses in a batch may come back in any order, so match them up by id
+        let mut responses: HashMap<RequestId, JsonRpcMessage> = responses
+            .into_iter()
+            .filter_map(|message| {
+                let id = match &message {
+                    JsonRpcMessage::Response(response) => response.id.clone(),
+                    JsonRpcMessage::Error(error) => error.id.clone(),
+                    _ => None,
+                }?;
+                Some((id, message))
+            })
+            .collect();
+
+        Ok(ids
+            .iter()
+            .map(|id| match responses.remove(id) {
+                Some(message) => Self::parse_response(id, message),
+                None => Err(Error::UnexpectedResponse(format!(
+                    "missing response for request {} in batch",
+                    id
+                ))),
+            })
+            .collect())
+    }
+
+    /// Check that a response message answers the request `id` and extract its result.
+    fn parse_response<R>(id: &RequestId, response_msg: JsonRpcMessage) -> Result<R, Error>
+    where
+        R: for<'de> Deserialize<'de>,
+    {
         match response_msg {
             JsonRpcMessage::Response(JsonRpcResponse {
-                id, result, error, ..
+                id: response_id,
+                result,
+                error,
+                ..
             }) => {
                 // Verify id matches
-                if id != Some(self.next_id.load(Ordering::SeqCst) - 1) {
+                if response_id.as_ref() != Some(id) {
                     return Err(Error::UnexpectedResponse(
                         "id mismatch for JsonRpcResponse".to_string(),
                     ));
@@ -178,8 +486,12 @@ where
                     Err(Error::UnexpectedResponse("missing result".to_string()))
                 }
             }
-            JsonRpcMessage::Error(JsonRpcError { id, error, .. }) => {
-                if id != Some(self.next_id.load(Ordering::SeqCst) - 1) {
+            JsonRpcMessage::Error(JsonRpcError {
+                id: response_id,
+                error,
+                ..
+            }) => {
+                if response_id.as_ref() != Some(id) {
                     return Err(Error::UnexpectedResponse(
                         "id mismatch for JsonRpcError".to_string(),
                     ));
@@ -230,6 +542,153 @@ where
     fn completed_initialization(&self) -> bool {
         self.server_capabilities.is_some()
     }
+
+    /// Check that the server lets clients subscribe to resources
+    fn check_subscribe_capability(&self) -> Result<(), Error> {
+        if !self.completed_initialization() {
+            return Err(Error::NotInitialized);
+        }
+        let subscribe = self
+            .server_capabilities
+            .as_ref()
+            .unwrap()
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.subscribe)
+            .unwrap_or(false);
+        if !subscribe {
+            return Err(Error::RpcError {
+                code: METHOD_NOT_FOUND,
+                message: "Server does not support resource subscriptions".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Send a `tools/call` request with the given params
+    async fn send_tool_call(&self, params: Value) -> Result<CallToolResult, Error> {
+        if !self.completed_initialization() {
+            return Err(Error::NotInitialized);
+        }
+        // If tools is not supported, return an error
+        if self.server_capabilities.as_ref().unwrap().tools.is_none() {
+            return Err(Error::RpcError {
+                code: METHOD_NOT_FOUND,
+                message: "Server does not support 'tools' capability".to_string(),
+            });
+        }
+
+        // TODO ERROR: check that if there is an error, we send back is_error: true with msg
+        // https://modelcontextprotocol.io/docs/concepts/tools#error-handling-2
+        self.send_request("tools/call", params).await
+    }
+}
+
+/// Stream the items of every page of a list. `fetch` gets the cursor of a page, None for
+/// the first one, and returns its items and the cursor of the next page.
+fn paginate<'a, T, F, Fut>(fetch: F) -> BoxStream<'a, Result<T, Error>>
+where
+    T: Send + 'a,
+    F: Fn(Option<String>) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<(Vec<T>, Option<String>), Error>> + Send + 'a,
+{
+    // The state is the cursor of the page to fetch next, None once the last page is in
+    stream::try_unfold(Some(None), move |cursor: Option<Option<String>>| {
+        let page = cursor.clone().map(&fetch);
+        async move {
+            let Some(page) = page else {
+                return Ok(None);
+            };
+            let (items, next_cursor) = page.await?;
+            // A server handing out the same cursor again would have us loop forever
+            if next_cursor.is_some() && next_cursor == cursor.flatten() {
+                return Err(Error::UnexpectedResponse(
+                    "server repeated a pagination cursor".to_string(),
+                ));
+            }
+            Ok(Some((items, next_cursor.map(Some))))
+        }
+    })
+    .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+    .try_flatten()
+    .boxed()
+}
+
+/// Sends `notifications/cancelled` for requests that are abandoned before their response
+/// arrives, because they timed out or the future awaiting them was dropped
+struct CancelOnDrop<S>
+where
+    S: Service<JsonRpcMessage, Response = JsonRpcMessage> + Clone + Send + Sync + 'static,
+    S::Future: Send,
+{
+    service: S,
+    ids: Vec<RequestId>,
+    reason: String,
+}
+
+impl<S> CancelOnDrop<S>
+where
+    S: Service<JsonRpcMessage, Response = JsonRpcMessage> + Clone + Send + Sync + 'static,
+    S::Future: Send,
+{
+    fn new(service: S, ids: Vec<RequestId>) -> Self {
+        Self {
+            service,
+            ids,
+            reason: "Request was abandoned by the client".to_string(),
+        }
+    }
+
+    fn set_reason(&mut self, error: &Error) {
+        self.reason = error.to_string();
+    }
+
+    /// The response arrived, there is nothing to cancel
+    fn disarm(&mut self) {
+        self.ids.clear();
+    }
+}
+
+impl<S> Drop for CancelOnDrop<S>
+where
+    S: Service<JsonRpcMessage, Response = JsonRpcMessage> + Clone + Send + Sync + 'static,
+    S::Future: Send,
+{
+    fn drop(&mut self) {
+        if self.ids.is_empty() {
+            return;
+        }
+        // Sending is async, so it has to happen on a task of its own
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let mut service = self.service.clone();
+        let notifications: Vec<_> = self
+            .ids
+            .drain(..)
+            .map(|id| {
+                JsonRpcMessage::Notification(JsonRpcNotification {
+                    jsonrpc: "2.0".to_string(),
+                    method: CancelledNotification::METHOD.to_string(),
+                    params: serde_json::to_value(CancelledNotification {
+                        request_id: id,
+                        reason: Some(self.reason.clone()),
+                        meta: None,
+                    })
+                    .ok(),
+                })
+            })
+            .collect();
+        runtime.spawn(async move {
+            for notification in notifications {
+                if service.ready().await.is_err() || service.call(notification).await.is_err() {
+                    tracing::debug!("Failed to send cancellation to the server");
+                    return;
+                }
+            }
+        });
+    }
 }
 
 #[async_trait::async_trait]
@@ -242,10 +701,23 @@ where
     async fn initialize(
         &mut self,
         info: ClientInfo,
-        capabilities: ClientCapabilities,
+        mut capabilities: ClientCapabilities,
     ) -> Result<InitializeResult, Error> {
+        if self.dispatcher.roots_provider().is_some() {
+            capabilities.roots.get_or_insert(RootsCapability {
+                list_changed: Some(true),
+            });
+        }
+        if self.dispatcher.sampling_handler().is_some() {
+            capabilities.sampling.get_or_insert_with(Default::default);
+        }
+        if self.dispatcher.elicitation_handler().is_some() {
+            capabilities
+                .elicitation
+                .get_or_insert_with(Default::default);
+        }
         let params = InitializeParams {
-            protocol_version: "1.0.0".into(),
+            protocol_version: ProtocolVersion::LATEST.to_string(),
             client_info: info,
             capabilities,
         };
@@ -253,12 +725,22 @@ where
             .send_request("initialize", serde_json::to_value(params)?)
             .await?;
 
+        // The server answers with a version of its own if it doesn't support ours, which
+        // leaves the connection unusable when we don't support it either
+        let protocol_version = result
+            .protocol_version
+            .parse::<ProtocolVersion>()
+            .map_err(|e| Error::UnsupportedProtocolVersion(e.0))?;
+
         self.send_notification("notifications/initialized", serde_json::json!({}))
             .await?;
 
+        self.protocol_version = Some(protocol_version);
         self.server_capabilities = Some(result.capabilities.clone());
 
         self.server_info = Some(result.server_info.clone());
+        self.dispatcher
+            .set_server_name(result.server_info.name.clone());
 
         Ok(result)
     }
@@ -281,6 +763,7 @@ where
             return Ok(ListResourcesResult {
                 resources: vec![],
                 next_cursor: None,
+                meta: None,
             });
         }
 
@@ -313,15 +796,53 @@ where
         self.send_request("resources/read", params).await
     }
 
-    async fn list_tools(&self, next_cursor: Option<String>) -> Result<ListToolsResult, Error> {
+    async fn subscribe_resource(&self, uri: &str) -> Result<(), Error> {
+        self.check_subscribe_capability()?;
+        let params = serde_json::to_value(SubscribeRequest {
+            uri: uri.to_string(),
+            meta: None,
+        })?;
+        let _: EmptyResult = self.send_request("resources/subscribe", params).await?;
+        Ok(())
+    }
+
+    async fn unsubscribe_resource(&self, uri: &str) -> Result<(), Error> {
+        self.check_subscribe_capability()?;
+        let params = serde_json::to_value(UnsubscribeRequest {
+            uri: uri.to_string(),
+            meta: None,
+        })?;
+        let _: EmptyResult = self.send_request("resources/unsubscribe", params).await?;
+        Ok(())
+    }
+
+    fn resource_updates(&self) -> BoxStream<'static, ResourceUpdatedNotification> {
+        self.dispatcher.resource_updates()
+    }
+
+    fn list_changed(&self) -> BoxStream<'static, ListChanged> {
+        self.dispatcher.list_changed()
+    }
+
+    async fn list_resource_templates(
+        &self,
+        next_cursor: Option<String>,
+    ) -> Result<ListResourceTemplatesResult, Error> {
         if !self.completed_initialization() {
             return Err(Error::NotInitialized);
         }
-        // If tools is not supported, return an empty list
-        if self.server_capabilities.as_ref().unwrap().tools.is_none() {
-            return Ok(ListToolsResult {
-                tools: vec![],
+        // If resources is not supported, return an empty list
+        if self
+            .server_capabilities
+            .as_ref()
+            .unwrap()
+            .resources
+            .is_none()
+        {
+            return Ok(ListResourceTemplatesResult {
+                resource_templates: vec![],
                 next_cursor: None,
+                meta: None,
             });
         }
 
@@ -329,26 +850,58 @@ where
             .map(|cursor| serde_json::json!({"cursor": cursor}))
             .unwrap_or_else(|| serde_json::json!({}));
 
-        self.send_request("tools/list", payload).await
+        self.send_request("resources/templates/list", payload).await
     }
 
-    async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult, Error> {
+    async fn list_tools(&self, next_cursor: Option<String>) -> Result<ListToolsResult, Error> {
         if !self.completed_initialization() {
             return Err(Error::NotInitialized);
         }
-        // If tools is not supported, return an error
+        // If tools is not supported, return an empty list
         if self.server_capabilities.as_ref().unwrap().tools.is_none() {
-            return Err(Error::RpcError {
-                code: METHOD_NOT_FOUND,
-                message: "Server does not support 'tools' capability".to_string(),
+            return Ok(ListToolsResult {
+                tools: vec![],
+                next_cursor: None,
+                meta: None,
             });
         }
 
+        let payload = next_cursor
+            .map(|cursor| serde_json::json!({"cursor": cursor}))
+            .unwrap_or_else(|| serde_json::json!({}));
+
+        self.send_request("tools/list", payload).await
+    }
+
+    async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult, Error> {
         let params = serde_json::json!({ "name": name, "arguments": arguments });
+        self.send_tool_call(params).await
+    }
 
-        // TODO ERROR: check that if there is an error, we send back is_error: true with msg
-        // https://modelcontextprotocol.io/docs/concepts/tools#error-handling-2
-        self.send_request("tools/call", params).await
+    async fn call_tool_with_meta(
+        &self,
+        name: &str,
+        arguments: Value,
+        meta: Meta,
+    ) -> Result<CallToolResult, Error> {
+        let params = serde_json::json!({ "name": name, "arguments": arguments, "_meta": meta });
+        self.send_tool_call(params).await
+    }
+
+    async fn call_tool_with_progress(
+        &self,
+        name: &str,
+        arguments: Value,
+        on_progress: ProgressCallback,
+    ) -> Result<CallToolResult, Error> {
+        // Progress is delivered until the call completes or is dropped
+        let token = ProgressToken::Number(self.next_id.fetch_add(1, Ordering::SeqCst).into());
+        let _progress = self
+            .dispatcher
+            .register_progress(token.clone(), on_progress);
+
+        self.call_tool_with_meta(name, arguments, Meta::new().with_progress_token(token))
+            .await
     }
 
     async fn list_prompts(&self, next_cursor: Option<String>) -> Result<ListPromptsResult, Error> {
@@ -388,4 +941,430 @@ where
 
         self.send_request("prompts/get", params).await
     }
+
+    async fn complete(
+        &self,
+        reference: Reference,
+        argument: &str,
+        value: &str,
+    ) -> Result<CompleteResult, Error> {
+        if !self.completed_initialization() {
+            return Err(Error::NotInitialized);
+        }
+
+        // If completions is not supported, return an error
+        if self
+            .server_capabilities
+            .as_ref()
+            .unwrap()
+            .completions
+            .is_none()
+        {
+            return Err(Error::RpcError {
+                code: METHOD_NOT_FOUND,
+                message: "Server does not support 'completions' capability".to_string(),
+            });
+        }
+
+        let params = serde_json::to_value(CompleteRequest {
+            reference,
+            argument: ArgumentInfo {
+                name: argument.to_string(),
+                value: value.to_string(),
+            },
+            meta: None,
+        })?;
+        self.send_request(CompleteRequest::METHOD, params).await
+    }
+
+    async fn set_log_level(&self, level: LoggingLevel) -> Result<(), Error> {
+        if !self.completed_initialization() {
+            return Err(Error::NotInitialized);
+        }
+
+        // If logging is not supported, return an error
+        if self.server_capabilities.as_ref().unwrap().logging.is_none() {
+            return Err(Error::RpcError {
+                code: METHOD_NOT_FOUND,
+                message: "Server does not support 'logging' capability".to_string(),
+            });
+        }
+
+        let params = serde_json::to_value(SetLevelRequest { level, meta: None })?;
+        let _: EmptyResult = self.send_request("logging/setLevel", params).await?;
+        Ok(())
+    }
+
+    async fn notify_roots_list_changed(&self) -> Result<(), Error> {
+        if !self.completed_initialization() {
+            return Err(Error::NotInitialized);
+        }
+
+        let params = serde_json::to_value(RootsListChangedNotification { meta: None })?;
+        self.send_notification(RootsListChangedNotification::METHOD, params)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use mcp_core::protocol::{ErrorData, INVALID_REQUEST};
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    /// Records every message sent and never answers requests
+    #[derive(Clone)]
+    struct SilentService {
+        sent: mpsc::UnboundedSender<JsonRpcMessage>,
+    }
+
+    impl Service<JsonRpcMessage> for SilentService {
+        type Response = JsonRpcMessage;
+        type Error = Error;
+        type Future = BoxFuture<'static, Result<JsonRpcMessage, Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, message: JsonRpcMessage) -> Self::Future {
+            let expects_response = message.expects_response();
+            let _ = self.sent.send(message);
+            Box::pin(async move {
+                if expects_response {
+                    futures::future::pending::<()>().await;
+                }
+                Ok(JsonRpcMessage::Nil)
+            })
+        }
+    }
+
+    /// Answers `initialize` with the given protocol version, `tools/call` with the
+    /// structured output `{"lines": 42}`, and accepts everything else
+    #[derive(Clone)]
+    struct VersionedService {
+        version: &'static str,
+    }
+
+    impl Service<JsonRpcMessage> for VersionedService {
+        type Response = JsonRpcMessage;
+        type Error = Error;
+        type Future = BoxFuture<'static, Result<JsonRpcMessage, Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, message: JsonRpcMessage) -> Self::Future {
+            let version = self.version;
+            Box::pin(async move {
+                let JsonRpcMessage::Request(request) = message else {
+                    return Ok(JsonRpcMessage::Nil);
+                };
+                let result = match request.method.as_str() {
+                    "tools/list" => {
+                        let cursor = request.params.as_ref().and_then(|p| p.get("cursor"));
+                        let (name, next_cursor) = match cursor {
+                            None => ("first", Some("page-2")),
+                            Some(_) => ("second", None),
+                        };
+                        serde_json::json!({
+                            "tools": [{ "name": name, "description": "", "inputSchema": {} }],
+                            "nextCursor": next_cursor,
+                        })
+                    }
+                    // Echo the request's metadata back on the result
+                    "tools/call" => serde_json::to_value(CallToolResult {
+                        meta: request.meta(),
+                        ..CallToolResult::structured(serde_json::json!({ "lines": 42 }))
+                    })?,
+                    _ => serde_json::json!({
+                        "protocolVersion": version,
+                        "capabilities": { "tools": {} },
+                        "serverInfo": { "name": "versioned", "version": "1.0.0" }
+                    }),
+                };
+                Ok(JsonRpcMessage::Response(JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(result),
+                    error: None,
+                }))
+            })
+        }
+    }
+
+    /// Answers a batch with its responses in reverse order, an error for `fail` and no
+    /// response at all for `skip`, or rejects the whole batch if it holds `reject`
+    #[derive(Clone)]
+    struct BatchService;
+
+    impl Service<JsonRpcMessage> for BatchService {
+        type Response = JsonRpcMessage;
+        type Error = Error;
+        type Future = BoxFuture<'static, Result<JsonRpcMessage, Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, message: JsonRpcMessage) -> Self::Future {
+            Box::pin(async move {
+                let JsonRpcMessage::Batch(messages) = message else {
+                    return Ok(JsonRpcMessage::Nil);
+                };
+                let error = |id, code, message: &str| {
+                    JsonRpcMessage::Error(JsonRpcError {
+                        jsonrpc: "2.0".to_string(),
+                        id,
+                        error: ErrorData {
+                            code,
+                            message: message.to_string(),
+                            data: None,
+                        },
+                    })
+                };
+                let mut responses = Vec::new();
+                for message in messages.into_iter().rev() {
+                    let JsonRpcMessage::Request(request) = message else {
+                        continue;
+                    };
+                    responses.push(match request.method.as_str() {
+                        "reject" => return Ok(error(None, INVALID_REQUEST, "Invalid Request")),
+                        "skip" => continue,
+                        "fail" => error(request.id, METHOD_NOT_FOUND, "Method not found"),
+                        method => JsonRpcMessage::Response(JsonRpcResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id: request.id,
+                            result: Some(serde_json::json!({ "method": method })),
+                            error: None,
+                        }),
+                    });
+                }
+                Ok(JsonRpcMessage::Batch(responses))
+            })
+        }
+    }
+
+    fn client_info() -> ClientInfo {
+        ClientInfo {
+            name: "test".to_string(),
+            version: "1.0.0".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initialize_checks_protocol_version() {
+        let mut client = McpClient::new(VersionedService {
+            version: "2024-11-05",
+        });
+        client
+            .initialize(client_info(), ClientCapabilities::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            client.protocol_version(),
+            Some(ProtocolVersion::V2024_11_05)
+        );
+
+        // Batches were only introduced after this version, so the requests go one by one
+        let results = client
+            .send_batch::<Value>(vec![
+                ("tools/list", serde_json::json!({})),
+                ("tools/list", serde_json::json!({ "cursor": "page-2" })),
+            ])
+            .await
+            .unwrap();
+        let names: Vec<_> = results
+            .into_iter()
+            .map(|result| result.unwrap()["tools"][0]["name"].clone())
+            .collect();
+        assert_eq!(names, vec!["first", "second"]);
+
+        let mut client = McpClient::new(VersionedService {
+            version: "2099-01-01",
+        });
+        let result = client
+            .initialize(client_info(), ClientCapabilities::default())
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedProtocolVersion(version)) if version == "2099-01-01"
+        ));
+        assert!(!client.completed_initialization());
+    }
+
+    #[tokio::test]
+    async fn test_send_batch() {
+        let client = McpClient::new(BatchService);
+        let results = client
+            .send_batch::<Value>(vec![
+                ("ping", serde_json::json!({})),
+                ("fail", serde_json::json!({})),
+                ("skip", serde_json::json!({})),
+                ("tools/list", serde_json::json!({})),
+            ])
+            .await
+            .unwrap();
+
+        // Results come back in the order of the requests, whatever the server's order
+        assert_eq!(results.len(), 4);
+        assert_eq!(
+            results[0].as_ref().unwrap(),
+            &serde_json::json!({ "method": "ping" })
+        );
+        assert!(matches!(
+            results[1],
+            Err(Error::RpcError {
+                code: METHOD_NOT_FOUND,
+                ..
+            })
+        ));
+        assert!(matches!(results[2], Err(Error::UnexpectedResponse(_))));
+        assert_eq!(
+            results[3].as_ref().unwrap(),
+            &serde_json::json!({ "method": "tools/list" })
+        );
+
+        let rejected = client
+            .send_batch::<Value>(vec![("reject", serde_json::json!({}))])
+            .await;
+        assert!(matches!(
+            rejected,
+            Err(Error::RpcError {
+                code: INVALID_REQUEST,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_as_parses_structured_content() {
+        #[derive(Deserialize)]
+        struct LineCount {
+            lines: u32,
+        }
+
+        let mut client = McpClient::new(VersionedService {
+            version: ProtocolVersion::LATEST.as_str(),
+        });
+        client
+            .initialize(client_info(), ClientCapabilities::default())
+            .await
+            .unwrap();
+
+        let count: LineCount = client
+            .call_tool_as("count", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(count.lines, 42);
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_with_meta() {
+        let mut client = McpClient::new(VersionedService {
+            version: ProtocolVersion::LATEST.as_str(),
+        });
+        client
+            .initialize(client_info(), ClientCapabilities::default())
+            .await
+            .unwrap();
+
+        let meta = Meta::new().with("example.com/trace", "abc");
+        let result = client
+            .call_tool_with_meta("count", serde_json::json!({}), meta.clone())
+            .await
+            .unwrap();
+        assert_eq!(result.meta, Some(meta));
+
+        let result = client
+            .call_tool("count", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result.meta, None);
+    }
+
+    #[tokio::test]
+    async fn test_list_all_tools_follows_cursors() {
+        let mut client = McpClient::new(VersionedService {
+            version: ProtocolVersion::LATEST.as_str(),
+        });
+        client
+            .initialize(client_info(), ClientCapabilities::default())
+            .await
+            .unwrap();
+
+        let names: Vec<String> = client
+            .list_all_tools()
+            .map_ok(|tool| tool.name)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_request_is_cancelled() {
+        let (sent, mut server_rx) = mpsc::unbounded_channel();
+        let client = McpClient::new(SilentService { sent });
+
+        let call = client.send_batch::<Value>(vec![("tools/call", serde_json::json!({}))]);
+        assert!(tokio::time::timeout(Duration::from_millis(10), call)
+            .await
+            .is_err());
+
+        let Some(JsonRpcMessage::Batch(batch)) = server_rx.recv().await else {
+            panic!("Expected the batch to be sent");
+        };
+        let JsonRpcMessage::Request(request) = &batch[0] else {
+            panic!("Expected a request in the batch");
+        };
+
+        match server_rx.recv().await {
+            Some(JsonRpcMessage::Notification(notification)) => {
+                assert_eq!(notification.method, CancelledNotification::METHOD);
+                let cancelled: CancelledNotification =
+                    serde_json::from_value(notification.params.unwrap()).unwrap();
+                assert_eq!(Some(cancelled.request_id), request.id.clone());
+            }
+            other => panic!("Expected a cancellation, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_times_out_with_service() {
+        let (sent, _server_rx) = mpsc::unbounded_channel();
+        let service = tower::ServiceBuilder::new()
+            .timeout(Duration::from_millis(10))
+            .service(SilentService { sent });
+        let client = McpClient::new(service);
+
+        let call = client.send_batch::<Value>(vec![("tools/list", serde_json::json!({}))]);
+        let result = tokio::time::timeout(Duration::from_secs(5), call)
+            .await
+            .expect("Expected the batch to time out with the service");
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_initialize_is_not_cancelled() {
+        let (sent, mut server_rx) = mpsc::unbounded_channel();
+        let mut client = McpClient::new(SilentService { sent });
+
+        let call = client.initialize(client_info(), ClientCapabilities::default());
+        assert!(tokio::time::timeout(Duration::from_millis(10), call)
+            .await
+            .is_err());
+
+        match server_rx.recv().await {
+            Some(JsonRpcMessage::Request(request)) => assert_eq!(request.method, "initialize"),
+            other => panic!("Expected the initialize request, got {:?}", other),
+        }
+        // Nothing else was sent once the call was dropped
+        drop(client);
+        assert!(server_rx.recv().await.is_none());
+    }
 }
@@ -0,0 +1,526 @@
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, StreamExt};
+use mcp_core::elicitation::ElicitRequest;
+use mcp_core::protocol::{
+    ErrorData, JsonRpcError, JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse,
+    ListChanged, ListRootsRequest, ListRootsResult, LoggingLevel, LoggingMessageNotification,
+    ProgressNotification, ProgressToken, ResourceUpdatedNotification, INTERNAL_ERROR,
+    INVALID_PARAMS, METHOD_NOT_FOUND,
+};
+use mcp_core::sampling::CreateMessageRequest;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use crate::elicitation::ElicitationHandler;
+use crate::roots::RootsProvider;
+use crate::sampling::SamplingHandler;
+use crate::transport::ServerMessages;
+
+/// Called with every progress notification the server sends about a request
+pub type ProgressCallback = Box<dyn Fn(ProgressNotification) + Send + Sync>;
+
+type ProgressCallbacks = HashMap<ProgressToken, Arc<dyn Fn(ProgressNotification) + Send + Sync>>;
+
+/// Sends the client's answer to a server request back to the server
+pub(crate) type Responder = Arc<dyn Fn(JsonRpcMessage) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// How many events a slow subscriber may fall behind before it misses some
+const EVENT_CAPACITY: usize = 64;
+
+/// Handles the requests and notifications the server sends on its own, routing them to
+/// whatever part of the client is waiting for them
+#[derive(Clone)]
+pub(crate) struct Dispatcher {
+    progress: Arc<Mutex<ProgressCallbacks>>,
+    server_name: Arc<Mutex<Option<String>>>,
+    resource_updates: broadcast::Sender<ResourceUpdatedNotification>,
+    list_changed: broadcast::Sender<ListChanged>,
+    sampling: Arc<Mutex<Option<Arc<dyn SamplingHandler>>>>,
+    roots: Arc<Mutex<Option<Arc<dyn RootsProvider>>>>,
+    elicitation: Arc<Mutex<Option<Arc<dyn ElicitationHandler>>>>,
+}
+
+impl Default for Dispatcher {
+    fn default() -> Self {
+        Self {
+            progress: Arc::default(),
+            server_name: Arc::default(),
+            resource_updates: broadcast::channel(EVENT_CAPACITY).0,
+            list_changed: broadcast::channel(EVENT_CAPACITY).0,
+            sampling: Arc::default(),
+            roots: Arc::default(),
+            elicitation: Arc::default(),
+        }
+    }
+}
+
+impl Dispatcher {
+    /// Start handling the messages a transport delivers from the server, answering its
+    /// requests through `respond`
+    pub(crate) fn spawn(&self, mut messages: ServerMessages, respond: Responder) {
+        let dispatcher = self.clone();
+        tokio::spawn(async move {
+            while let Some(message) = messages.recv().await {
+                match message {
+                    // Requests can take a while, e.g. to sample an LLM, so don't let them
+                    // hold up the notifications behind them
+                    JsonRpcMessage::Request(request) => {
+                        let dispatcher = dispatcher.clone();
+                        let respond = respond.clone();
+                        tokio::spawn(async move {
+                            if let Some(response) = dispatcher.handle_request(request).await {
+                                respond(response).await;
+                            }
+                        });
+                    }
+                    message => dispatcher.dispatch(message),
+                }
+            }
+        });
+    }
+
+    pub(crate) fn dispatch(&self, message: JsonRpcMessage) {
+        match message {
+            JsonRpcMessage::Notification(notification) => self.handle_notification(notification),
+            other => {
+                tracing::debug!(message = ?other, "Ignoring message from server");
+            }
+        }
+    }
+
+    /// Answer a request from the server, returning the response to send back. Requests
+    /// without an id get no response.
+    pub(crate) async fn handle_request(&self, request: JsonRpcRequest) -> Option<JsonRpcMessage> {
+        let id = request.id?;
+        let params = request.params.unwrap_or_else(|| serde_json::json!({}));
+        let result = match request.method.as_str() {
+            ListRootsRequest::METHOD => match self.roots_provider() {
+                Some(provider) => {
+                    call(params, |_: ListRootsRequest| async move {
+                        let roots = provider.list_roots().await?;
+                        Ok(ListRootsResult { roots, meta: None })
+                    })
+                    .await
+                }
+                None => Err(method_not_found(&request.method)),
+            },
+            CreateMessageRequest::METHOD => match self.sampling_handler() {
+                Some(handler) => {
+                    call(params, |request| async move {
+                        handler.create_message(request).await
+                    })
+                    .await
+                }
+                None => Err(method_not_found(&request.method)),
+            },
+            ElicitRequest::METHOD => match self.elicitation_handler() {
+                Some(handler) => {
+                    call(
+                        params,
+                        |request| async move { handler.elicit(request).await },
+                    )
+                    .await
+                }
+                None => Err(method_not_found(&request.method)),
+            },
+            method => Err(method_not_found(method)),
+        };
+
+        Some(match result {
+            Ok(result) => JsonRpcMessage::Response(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: Some(id),
+                result: Some(result),
+                error: None,
+            }),
+            Err(error) => JsonRpcMessage::Error(JsonRpcError {
+                jsonrpc: "2.0".to_string(),
+                id: Some(id),
+                error,
+            }),
+        })
+    }
+
+    /// Answer the server's sampling requests with `handler`
+    pub(crate) fn set_sampling_handler(&self, handler: Arc<dyn SamplingHandler>) {
+        *self
+            .sampling
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(handler);
+    }
+
+    pub(crate) fn sampling_handler(&self) -> Option<Arc<dyn SamplingHandler>> {
+        self.sampling
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Answer the server's `elicitation/create` requests with `handler`
+    pub(crate) fn set_elicitation_handler(&self, handler: Arc<dyn ElicitationHandler>) {
+        *self
+            .elicitation
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(handler);
+    }
+
+    pub(crate) fn elicitation_handler(&self) -> Option<Arc<dyn ElicitationHandler>> {
+        self.elicitation
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Answer the server's `roots/list` requests with `provider`
+    pub(crate) fn set_roots_provider(&self, provider: Arc<dyn RootsProvider>) {
+        *self
+            .roots
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(provider);
+    }
+
+    pub(crate) fn roots_provider(&self) -> Option<Arc<dyn RootsProvider>> {
+        self.roots
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn handle_notification(&self, notification: JsonRpcNotification) {
+        match notification.method.as_str() {
+            ProgressNotification::METHOD => {
+                let params = notification.params.unwrap_or_default();
+                let progress: ProgressNotification = match serde_json::from_value(params) {
+                    Ok(progress) => progress,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Invalid progress notification");
+                        return;
+                    }
+                };
+                let callback = self.progress().get(&progress.progress_token).cloned();
+                match callback {
+                    Some(callback) => callback(progress),
+                    None => tracing::debug!(
+                        token = %progress.progress_token,
+                        "Progress for a request that is no longer running"
+                    ),
+                }
+            }
+            LoggingMessageNotification::METHOD => {
+                let params = notification.params.unwrap_or_default();
+                match serde_json::from_value(params) {
+                    Ok(message) => self.emit_log(message),
+                    Err(e) => tracing::warn!(error = %e, "Invalid log message notification"),
+                }
+            }
+            ResourceUpdatedNotification::METHOD => {
+                let params = notification.params.unwrap_or_default();
+                match serde_json::from_value(params) {
+                    // Having no subscribers to the stream is fine
+                    Ok(update) => {
+                        let _ = self.resource_updates.send(update);
+                    }
+                    Err(e) => tracing::warn!(error = %e, "Invalid resource update notification"),
+                }
+            }
+            method => match ListChanged::from_method(method) {
+                Some(list) => {
+                    let _ = self.list_changed.send(list);
+                }
+                None => tracing::debug!(method, "Ignoring notification from server"),
+            },
+        }
+    }
+
+    /// A stream of the resource updates received from now on
+    pub(crate) fn resource_updates(&self) -> BoxStream<'static, ResourceUpdatedNotification> {
+        events(self.resource_updates.subscribe())
+    }
+
+    /// A stream of the lists the server announces changes to from now on
+    pub(crate) fn list_changed(&self) -> BoxStream<'static, ListChanged> {
+        events(self.list_changed.subscribe())
+    }
+
+    /// Name the server in the log messages it sends, once it has introduced itself
+    pub(crate) fn set_server_name(&self, name: String) {
+        *self
+            .server_name
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(name);
+    }
+
+    /// Re-emit a log message from the server as a `tracing` event, so that server logs
+    /// end up wherever the client's own logs go
+    fn emit_log(&self, message: LoggingMessageNotification) {
+        let server = self
+            .server_name
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+            .unwrap_or_default();
+        let logger = message.logger.unwrap_or_default();
+        // Plain strings are the common case, don't wrap them in quotes
+        let data = match message.data {
+            serde_json::Value::String(text) => text,
+            data => data.to_string(),
+        };
+
+        match message.level {
+            LoggingLevel::Debug => tracing::debug!(server, logger, "{}", data),
+            LoggingLevel::Info | LoggingLevel::Notice => {
+                tracing::info!(server, logger, "{}", data)
+            }
+            LoggingLevel::Warning => tracing::warn!(server, logger, "{}", data),
+            LoggingLevel::Error
+            | LoggingLevel::Critical
+            | LoggingLevel::Alert
+            | LoggingLevel::Emergency => tracing::error!(server, logger, "{}", data),
+        }
+    }
+
+    /// Call `callback` with the progress reported for `token` until the guard is dropped
+    pub(crate) fn register_progress(
+        &self,
+        token: ProgressToken,
+        callback: ProgressCallback,
+    ) -> ProgressGuard {
+        self.progress().insert(token.clone(), Arc::from(callback));
+        ProgressGuard {
+            dispatcher: self.clone(),
+            token,
+        }
+    }
+
+    fn progress(&self) -> std::sync::MutexGuard<'_, ProgressCallbacks> {
+        self.progress
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Parse the params of a server request, run the handler on them and serialize its result
+async fn call<P, R, F, Fut>(params: Value, handler: F) -> Result<Value, ErrorData>
+where
+    P: DeserializeOwned,
+    R: Serialize,
+    F: FnOnce(P) -> Fut,
+    Fut: std::future::Future<Output = Result<R, ErrorData>>,
+{
+    let params = serde_json::from_value(params).map_err(|e| ErrorData {
+        code: INVALID_PARAMS,
+        message: e.to_string(),
+        data: None,
+    })?;
+    let result = handler(params).await?;
+    serde_json::to_value(result).map_err(|e| ErrorData {
+        code: INTERNAL_ERROR,
+        message: format!("JSON serialization error: {}", e),
+        data: None,
+    })
+}
+
+fn method_not_found(method: &str) -> ErrorData {
+    ErrorData {
+        code: METHOD_NOT_FOUND,
+        message: format!("Method not found: {}", method),
+        data: None,
+    }
+}
+
+/// Turn a broadcast receiver into a stream, skipping over events missed by lagging behind
+fn events<T: Clone + Send + 'static>(receiver: broadcast::Receiver<T>) -> BoxStream<'static, T> {
+    futures::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "Dropped events from server");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+    .boxed()
+}
+
+/// Stops delivering progress for a request once it completes, or is abandoned
+pub(crate) struct ProgressGuard {
+    dispatcher: Dispatcher,
+    token: ProgressToken,
+}
+
+impl Drop for ProgressGuard {
+    fn drop(&mut self) {
+        self.dispatcher.progress().remove(&self.token);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::protocol::{RequestId, Root};
+    use mcp_core::role::Role;
+    use mcp_core::sampling::CreateMessageResult;
+    use serde_json::json;
+
+    fn progress_message(token: u64, progress: f64) -> JsonRpcMessage {
+        JsonRpcMessage::Notification(JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: ProgressNotification::METHOD.to_string(),
+            params: Some(json!({ "progressToken": token, "progress": progress })),
+        })
+    }
+
+    #[test]
+    fn test_progress_routed_by_token() {
+        let dispatcher = Dispatcher::default();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let sink = seen.clone();
+        let guard = dispatcher.register_progress(
+            ProgressToken::Number(1.into()),
+            Box::new(move |p| sink.lock().unwrap().push(p.progress)),
+        );
+
+        dispatcher.dispatch(progress_message(1, 0.5));
+        dispatcher.dispatch(progress_message(2, 0.7));
+        drop(guard);
+        dispatcher.dispatch(progress_message(1, 1.0));
+
+        assert_eq!(*seen.lock().unwrap(), vec![0.5]);
+    }
+
+    struct EchoSampler;
+
+    #[async_trait::async_trait]
+    impl SamplingHandler for EchoSampler {
+        async fn create_message(
+            &self,
+            request: CreateMessageRequest,
+        ) -> Result<CreateMessageResult, ErrorData> {
+            Ok(CreateMessageResult {
+                role: Role::Assistant,
+                content: request.messages[0].content.clone(),
+                model: "echo".to_string(),
+                stop_reason: Some("endTurn".to_string()),
+                meta: None,
+            })
+        }
+    }
+
+    fn sampling_request() -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(RequestId::Number(7.into())),
+            method: CreateMessageRequest::METHOD.to_string(),
+            params: Some(json!({
+                "messages": [{"role": "user", "content": {"type": "text", "text": "Hi"}}],
+                "maxTokens": 10
+            })),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sampling_request_answered_by_handler() {
+        let dispatcher = Dispatcher::default();
+
+        let Some(JsonRpcMessage::Error(error)) =
+            dispatcher.handle_request(sampling_request()).await
+        else {
+            panic!("Expected an error without a sampling handler");
+        };
+        assert_eq!(error.error.code, METHOD_NOT_FOUND);
+
+        dispatcher.set_sampling_handler(Arc::new(EchoSampler));
+        let Some(JsonRpcMessage::Response(response)) =
+            dispatcher.handle_request(sampling_request()).await
+        else {
+            panic!("Expected a response from the sampling handler");
+        };
+        assert_eq!(response.id, Some(RequestId::Number(7.into())));
+        assert_eq!(
+            response.result.unwrap(),
+            json!({
+                "role": "assistant",
+                "content": {"type": "text", "text": "Hi"},
+                "model": "echo",
+                "stopReason": "endTurn"
+            })
+        );
+    }
+
+    struct ProjectRoots;
+
+    #[async_trait::async_trait]
+    impl RootsProvider for ProjectRoots {
+        async fn list_roots(&self) -> Result<Vec<Root>, ErrorData> {
+            Ok(vec![Root::new("file:///project").with_name("Project")])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_roots_request_answered_by_provider() {
+        let dispatcher = Dispatcher::default();
+        dispatcher.set_roots_provider(Arc::new(ProjectRoots));
+
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(RequestId::Number(3.into())),
+            method: ListRootsRequest::METHOD.to_string(),
+            params: None,
+        };
+        let Some(JsonRpcMessage::Response(response)) = dispatcher.handle_request(request).await
+        else {
+            panic!("Expected a response from the roots provider");
+        };
+        assert_eq!(
+            response.result.unwrap(),
+            json!({"roots": [{"uri": "file:///project", "name": "Project"}]})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resource_updates_stream() {
+        let dispatcher = Dispatcher::default();
+        let mut updates = dispatcher.resource_updates();
+
+        dispatcher.dispatch(JsonRpcMessage::Notification(JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: ResourceUpdatedNotification::METHOD.to_string(),
+            params: Some(json!({ "uri": "file:///a.txt" })),
+        }));
+
+        assert_eq!(
+            updates.next().await,
+            Some(ResourceUpdatedNotification {
+                uri: "file:///a.txt".to_string(),
+                meta: None,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_changed_stream() {
+        let dispatcher = Dispatcher::default();
+        let mut changes = dispatcher.list_changed();
+
+        for method in [
+            "notifications/tools/list_changed",
+            "notifications/unknown",
+            "notifications/prompts/list_changed",
+        ] {
+            dispatcher.dispatch(JsonRpcMessage::Notification(JsonRpcNotification {
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params: None,
+            }));
+        }
+
+        assert_eq!(changes.next().await, Some(ListChanged::Tools));
+        assert_eq!(changes.next().await, Some(ListChanged::Prompts));
+    }
+}
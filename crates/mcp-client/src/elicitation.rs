@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use mcp_core::elicitation::{ElicitRequest, ElicitResult};
+use mcp_core::protocol::ErrorData;
+
+/// Answers the `elicitation/create` requests a server sends to ask the user for input,
+/// such as a confirmation or a missing field.
+///
+/// The handler should show the request's message and render its schema as a form, then
+/// return what the user submitted with [`ElicitResult::accept`], or
+/// [`ElicitResult::decline`] and [`ElicitResult::cancel`] when they refuse or dismiss it.
+/// Servers must not use elicitation to ask for sensitive information.
+///
+/// Register it with
+/// [`McpClient::with_elicitation_handler`](crate::McpClient::with_elicitation_handler), the
+/// client then announces the elicitation capability when it initializes.
+#[async_trait]
+pub trait ElicitationHandler: Send + Sync {
+    async fn elicit(&self, request: ElicitRequest) -> Result<ElicitResult, ErrorData>;
+}
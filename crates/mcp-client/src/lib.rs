@@ -1,7 +1,15 @@
 pub mod client;
+mod dispatch;
+pub mod elicitation;
+pub mod roots;
+pub mod sampling;
 pub mod service;
 pub mod transport;
 
 pub use client::{ClientCapabilities, ClientInfo, Error, McpClient, McpClientTrait};
+pub use dispatch::ProgressCallback;
+pub use elicitation::ElicitationHandler;
+pub use roots::RootsProvider;
+pub use sampling::SamplingHandler;
 pub use service::McpService;
-pub use transport::{SseTransport, StdioTransport, Transport, TransportHandle};
+pub use transport::{ServerMessages, SseTransport, StdioTransport, Transport, TransportHandle};
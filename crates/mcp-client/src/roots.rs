@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+use mcp_core::protocol::{ErrorData, Root};
+
+/// Answers the `roots/list` requests a server sends to learn which directories and files
+/// it may operate on.
+///
+/// Register it with [`McpClient::with_roots_provider`](crate::McpClient::with_roots_provider),
+/// the client then announces the roots capability when it initializes. Whenever the roots
+/// change, tell the server with
+/// [`McpClientTrait::notify_roots_list_changed`](crate::McpClientTrait::notify_roots_list_changed).
+#[async_trait]
+pub trait RootsProvider: Send + Sync {
+    async fn list_roots(&self) -> Result<Vec<Root>, ErrorData>;
+}
@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+use mcp_core::protocol::ErrorData;
+use mcp_core::sampling::{CreateMessageRequest, CreateMessageResult};
+
+/// Answers the `sampling/createMessage` requests a server sends to have the client's LLM
+/// generate a message.
+///
+/// The handler is in charge of picking a model from the server's preferences, and should
+/// let the user review the request before sending it, since servers can't see or choose
+/// the model themselves. Return an error to refuse the request.
+///
+/// Register it with [`McpClient::with_sampling_handler`](crate::McpClient::with_sampling_handler),
+/// the client then announces the sampling capability when it initializes.
+#[async_trait]
+pub trait SamplingHandler: Send + Sync {
+    async fn create_message(
+        &self,
+        request: CreateMessageRequest,
+    ) -> Result<CreateMessageResult, ErrorData>;
+}
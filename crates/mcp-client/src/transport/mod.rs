@@ -1,6 +1,7 @@
 use async_trait::async_trait;
-use mcp_core::protocol::JsonRpcMessage;
+use mcp_core::protocol::{JsonRpcMessage, JsonRpcRequest, RequestId};
 use std::collections::HashMap;
+use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::{mpsc, oneshot, RwLock};
 
@@ -20,7 +21,9 @@ pub enum Error {
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
-    #[error("Unsupported message type. JsonRpcMessage can only be Request or Notification.")]
+    #[error(
+        "Unsupported message type. JsonRpcMessage can only be Request, Notification, Response, Error or Batch."
+    )]
     UnsupportedMessage,
 
     #[error("Stdio process error: {0}")]
@@ -55,9 +58,34 @@ pub trait Transport {
     async fn close(&self) -> Result<(), Error>;
 }
 
+/// Requests and notifications the server sends on its own initiative, rather than in
+/// response to a client request
+pub type ServerMessages = mpsc::UnboundedReceiver<JsonRpcMessage>;
+
 #[async_trait]
 pub trait TransportHandle: Send + Sync + Clone + 'static {
     async fn send(&self, message: JsonRpcMessage) -> Result<JsonRpcMessage, Error>;
+
+    /// Take the messages the server sends on its own, to be handed to the client. They
+    /// can only be taken once, later calls return None, as do transports that don't
+    /// deliver them.
+    fn take_server_messages(&self) -> Option<ServerMessages> {
+        None
+    }
+}
+
+/// Holds the receiving end of the server messages until the client takes it
+#[derive(Clone)]
+pub(crate) struct ServerMessagesSlot(Arc<std::sync::Mutex<Option<ServerMessages>>>);
+
+impl ServerMessagesSlot {
+    pub(crate) fn new(messages: ServerMessages) -> Self {
+        Self(Arc::new(std::sync::Mutex::new(Some(messages))))
+    }
+
+    pub(crate) fn take(&self) -> Option<ServerMessages> {
+        self.0.lock().ok()?.take()
+    }
 }
 
 // Helper function that contains the common send implementation
@@ -75,21 +103,63 @@ pub async fn send_message(
             sender.send(msg).await.map_err(|_| Error::ChannelClosed)?;
             Ok(response.await.map_err(|_| Error::ChannelClosed)??)
         }
-        JsonRpcMessage::Notification(notification) => {
+        // Nothing answers notifications, nor the client's own answers to server requests
+        JsonRpcMessage::Notification(_)
+        | JsonRpcMessage::Response(_)
+        | JsonRpcMessage::Error(_) => {
             let msg = TransportMessage {
-                message: JsonRpcMessage::Notification(notification),
+                message,
                 response_tx: None,
             };
             sender.send(msg).await.map_err(|_| Error::ChannelClosed)?;
             Ok(JsonRpcMessage::Nil)
         }
+        JsonRpcMessage::Batch(messages) => {
+            let outgoing = |m: &JsonRpcMessage| {
+                matches!(
+                    m,
+                    JsonRpcMessage::Request(_) | JsonRpcMessage::Notification(_)
+                )
+            };
+            if messages.is_empty() || !messages.iter().all(outgoing) {
+                return Err(Error::UnsupportedMessage);
+            }
+            let message = JsonRpcMessage::Batch(messages);
+
+            // A batch of notifications only gets no reply, same as a single notification
+            if !message.expects_response() {
+                let msg = TransportMessage {
+                    message,
+                    response_tx: None,
+                };
+                sender.send(msg).await.map_err(|_| Error::ChannelClosed)?;
+                return Ok(JsonRpcMessage::Nil);
+            }
+
+            let (respond_to, response) = oneshot::channel();
+            let msg = TransportMessage {
+                message,
+                response_tx: Some(respond_to),
+            };
+            sender.send(msg).await.map_err(|_| Error::ChannelClosed)?;
+            Ok(response.await.map_err(|_| Error::ChannelClosed)??)
+        }
         _ => Err(Error::UnsupportedMessage),
     }
 }
 
+type ResponseSender = oneshot::Sender<Result<JsonRpcMessage, Error>>;
+
+/// A batch waiting for the server's reply, which answers all of its requests at once
+struct PendingBatch {
+    ids: Vec<RequestId>,
+    sender: ResponseSender,
+}
+
 // A data structure to store pending requests and their response channels
 pub struct PendingRequests {
-    requests: RwLock<HashMap<String, oneshot::Sender<Result<JsonRpcMessage, Error>>>>,
+    requests: RwLock<HashMap<RequestId, ResponseSender>>,
+    batches: RwLock<Vec<PendingBatch>>,
 }
 
 impl Default for PendingRequests {
@@ -102,21 +172,133 @@ impl PendingRequests {
     pub fn new() -> Self {
         Self {
             requests: RwLock::new(HashMap::new()),
+            batches: RwLock::new(Vec::new()),
         }
     }
 
-    pub async fn insert(&self, id: String, sender: oneshot::Sender<Result<JsonRpcMessage, Error>>) {
-        self.requests.write().await.insert(id, sender);
+    pub async fn insert(&self, id: RequestId, sender: ResponseSender) {
+        let mut requests = self.requests.write().await;
+        // Forget the requests nobody is waiting for anymore, e.g. after a timeout
+        requests.retain(|_, sender| !sender.is_closed());
+        requests.insert(id, sender);
     }
 
-    pub async fn respond(&self, id: &str, response: Result<JsonRpcMessage, Error>) {
+    pub async fn respond(&self, id: &RequestId, response: Result<JsonRpcMessage, Error>) {
         if let Some(tx) = self.requests.write().await.remove(id) {
             let _ = tx.send(response);
         }
     }
 
+    /// Register the response channel for an outgoing message. For a batch, the channel
+    /// receives the `Batch` the server answers it with, which may lack the responses to
+    /// some of its requests, or the error the server sends instead of a batch.
+    pub async fn insert_message(&self, message: &JsonRpcMessage, sender: ResponseSender) {
+        match message {
+            JsonRpcMessage::Request(JsonRpcRequest { id: Some(id), .. }) => {
+                self.insert(id.clone(), sender).await;
+            }
+            JsonRpcMessage::Batch(messages) => {
+                let ids = messages
+                    .iter()
+                    .filter_map(|message| match message {
+                        JsonRpcMessage::Request(JsonRpcRequest { id: Some(id), .. }) => {
+                            Some(id.clone())
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                let mut batches = self.batches.write().await;
+                // Forget the batches nobody is waiting for anymore, e.g. after a timeout
+                batches.retain(|batch| !batch.sender.is_closed());
+                batches.push(PendingBatch { ids, sender });
+            }
+            _ => {}
+        }
+    }
+
+    /// Route an incoming response, error or batch of them to the matching pending requests
+    pub async fn respond_message(&self, message: JsonRpcMessage) {
+        match message {
+            JsonRpcMessage::Response(ref response) => {
+                if let Some(id) = response.id.clone() {
+                    self.respond(&id, Ok(message)).await;
+                }
+            }
+            JsonRpcMessage::Error(ref error) => match error.id.clone() {
+                Some(id) => self.respond(&id, Ok(message)).await,
+                // An error without an id answers a batch the server couldn't handle at
+                // all. It doesn't say which one, so every batch waiting gets it.
+                None => {
+                    for batch in self.batches.write().await.drain(..) {
+                        let _ = batch.sender.send(Ok(message.clone()));
+                    }
+                }
+            },
+            JsonRpcMessage::Batch(messages) => {
+                let answers = |batch: &PendingBatch| {
+                    messages.iter().any(|message| {
+                        let id = match message {
+                            JsonRpcMessage::Response(response) => response.id.as_ref(),
+                            JsonRpcMessage::Error(error) => error.id.as_ref(),
+                            _ => None,
+                        };
+                        id.is_some_and(|id| batch.ids.contains(id))
+                    })
+                };
+                let mut batches = self.batches.write().await;
+                if let Some(index) = batches.iter().position(answers) {
+                    let batch = batches.remove(index);
+                    let _ = batch.sender.send(Ok(JsonRpcMessage::Batch(messages)));
+                    return;
+                }
+                drop(batches);
+
+                // Not the reply to one of our batches, answer the requests one by one
+                for message in messages {
+                    Box::pin(self.respond_message(message)).await;
+                }
+            }
+            _ => {} // TODO: Handle other variants (Request, etc.)
+        }
+    }
+
     pub async fn clear(&self) {
         self.requests.write().await.clear();
+        self.batches.write().await.clear();
+    }
+}
+
+/// Route a message read from the server. Responses go to the pending requests waiting for
+/// them, while requests and notifications the server sends on its own are forwarded to
+/// `server_messages`.
+pub async fn route_incoming_message(
+    pending_requests: &PendingRequests,
+    server_messages: &mpsc::UnboundedSender<JsonRpcMessage>,
+    message: JsonRpcMessage,
+) {
+    match message {
+        JsonRpcMessage::Request(_) | JsonRpcMessage::Notification(_) => {
+            // Nobody listening for server messages is fine, they are simply dropped
+            let _ = server_messages.send(message);
+        }
+        JsonRpcMessage::Batch(messages) => {
+            let (incoming, responses): (Vec<_>, Vec<_>) =
+                messages.into_iter().partition(|message| {
+                    matches!(
+                        message,
+                        JsonRpcMessage::Request(_) | JsonRpcMessage::Notification(_)
+                    )
+                });
+            for message in incoming {
+                let _ = server_messages.send(message);
+            }
+            if !responses.is_empty() {
+                pending_requests
+                    .respond_message(JsonRpcMessage::Batch(responses))
+                    .await;
+            }
+        }
+        message => pending_requests.respond_message(message).await,
     }
 }
 
@@ -125,3 +307,79 @@ pub use stdio::StdioTransport;
 
 pub mod sse;
 pub use sse::SseTransport;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::protocol::{ErrorData, JsonRpcError, JsonRpcResponse, INVALID_REQUEST};
+
+    fn batch(ids: &[u64]) -> JsonRpcMessage {
+        JsonRpcMessage::Batch(
+            ids.iter()
+                .map(|&id| {
+                    JsonRpcMessage::Request(JsonRpcRequest {
+                        jsonrpc: "2.0".to_string(),
+                        id: Some(RequestId::Number(id.into())),
+                        method: "ping".to_string(),
+                        params: None,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_batch_reply_with_missing_response() {
+        let pending = PendingRequests::new();
+        let (tx, rx) = oneshot::channel();
+        pending.insert_message(&batch(&[1, 2]), tx).await;
+
+        // The server left out the response to request 2, the batch still completes
+        let reply = JsonRpcMessage::Batch(vec![JsonRpcMessage::Response(JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(RequestId::Number(1.into())),
+            result: Some(serde_json::json!({})),
+            error: None,
+        })]);
+        pending.respond_message(reply.clone()).await;
+        assert_eq!(rx.await.unwrap().unwrap(), reply);
+    }
+
+    #[tokio::test]
+    async fn test_error_without_id_fails_batches() {
+        let pending = PendingRequests::new();
+        let (first_tx, first_rx) = oneshot::channel();
+        let (second_tx, second_rx) = oneshot::channel();
+        pending.insert_message(&batch(&[1, 2]), first_tx).await;
+        pending.insert_message(&batch(&[3]), second_tx).await;
+
+        let error = JsonRpcMessage::Error(JsonRpcError {
+            jsonrpc: "2.0".to_string(),
+            id: None,
+            error: ErrorData {
+                code: INVALID_REQUEST,
+                message: "Invalid Request".to_string(),
+                data: None,
+            },
+        });
+        pending.respond_message(error.clone()).await;
+        assert_eq!(first_rx.await.unwrap().unwrap(), error);
+        assert_eq!(second_rx.await.unwrap().unwrap(), error);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_requests_are_forgotten() {
+        let pending = PendingRequests::new();
+        let (abandoned, rx) = oneshot::channel();
+        pending.insert(RequestId::from(1u64), abandoned).await;
+        drop(rx);
+
+        let (waiting, _rx) = oneshot::channel();
+        pending.insert(RequestId::from(2u64), waiting).await;
+        let requests = pending.requests.read().await;
+        assert_eq!(
+            requests.keys().collect::<Vec<_>>(),
+            vec![&RequestId::from(2u64)]
+        );
+    }
+}
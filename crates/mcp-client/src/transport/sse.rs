@@ -1,8 +1,11 @@
-use crate::transport::{Error, PendingRequests, TransportMessage};
+use crate::transport::{
+    route_incoming_message, Error, PendingRequests, ServerMessages, ServerMessagesSlot,
+    TransportMessage,
+};
 use async_trait::async_trait;
 use eventsource_client::{Client, SSE};
 use futures::TryStreamExt;
-use mcp_core::protocol::{JsonRpcMessage, JsonRpcRequest};
+use mcp_core::protocol::JsonRpcMessage;
 use reqwest::Client as HttpClient;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -24,6 +27,8 @@ pub struct SseActor {
     receiver: mpsc::Receiver<TransportMessage>,
     /// Map of request-id -> oneshot sender
     pending_requests: Arc<PendingRequests>,
+    /// Forwards requests and notifications initiated by the server
+    server_messages: mpsc::UnboundedSender<JsonRpcMessage>,
     /// Base SSE URL
     sse_url: String,
     /// For sending HTTP POST requests
@@ -36,12 +41,14 @@ impl SseActor {
     pub fn new(
         receiver: mpsc::Receiver<TransportMessage>,
         pending_requests: Arc<PendingRequests>,
+        server_messages: mpsc::UnboundedSender<JsonRpcMessage>,
         sse_url: String,
         post_endpoint: Arc<RwLock<Option<String>>>,
     ) -> Self {
         Self {
             receiver,
             pending_requests,
+            server_messages,
             sse_url,
             post_endpoint,
             http_client: HttpClient::new(),
@@ -56,6 +63,7 @@ impl SseActor {
             Self::handle_incoming_messages(
                 self.sse_url.clone(),
                 Arc::clone(&self.pending_requests),
+                self.server_messages,
                 Arc::clone(&self.post_endpoint)
             ),
             Self::handle_outgoing_messages(
@@ -70,10 +78,12 @@ impl SseActor {
     /// Continuously reads SSE events from `sse_url`.
     /// - If an `endpoint` event is received, store it in `post_endpoint`.
     /// - If a `message` event is received, parse it as `JsonRpcMessage`
-    ///   and respond to pending requests if it's a `Response`.
+    ///   and respond to pending requests if it's a `Response`, or forward it
+    ///   to `server_messages` if the server initiated it.
     async fn handle_incoming_messages(
         sse_url: String,
         pending_requests: Arc<PendingRequests>,
+        server_messages: mpsc::UnboundedSender<JsonRpcMessage>,
         post_endpoint: Arc<RwLock<Option<String>>>,
     ) {
         let client = match eventsource_client::ClientBuilder::for_url(&sse_url) {
@@ -111,23 +121,8 @@ impl SseActor {
                     // Attempt to parse the SSE data as a JsonRpcMessage
                     match serde_json::from_str::<JsonRpcMessage>(&e.data) {
                         Ok(message) => {
-                            match &message {
-                                JsonRpcMessage::Response(response) => {
-                                    if let Some(id) = &response.id {
-                                        pending_requests
-                                            .respond(&id.to_string(), Ok(message))
-                                            .await;
-                                    }
-                                }
-                                JsonRpcMessage::Error(error) => {
-                                    if let Some(id) = &error.id {
-                                        pending_requests
-                                            .respond(&id.to_string(), Ok(message))
-                                            .await;
-                                    }
-                                }
-                                _ => {} // TODO: Handle other variants (Request, etc.)
-                            }
+                            route_incoming_message(&pending_requests, &server_messages, message)
+                                .await;
                         }
                         Err(err) => {
                             warn!("Failed to parse SSE message: {err}");
@@ -174,13 +169,11 @@ impl SseActor {
                 }
             };
 
-            // If it's a request, store the channel so we can respond later
+            // If it's a request or batch, store the channel so we can respond later
             if let Some(response_tx) = transport_msg.response_tx {
-                if let JsonRpcMessage::Request(JsonRpcRequest { id: Some(id), .. }) =
-                    &transport_msg.message
-                {
-                    pending_requests.insert(id.to_string(), response_tx).await;
-                }
+                pending_requests
+                    .insert_message(&transport_msg.message, response_tx)
+                    .await;
             }
 
             // Perform the HTTP POST
@@ -218,6 +211,7 @@ impl SseActor {
 #[derive(Clone)]
 pub struct SseTransportHandle {
     sender: mpsc::Sender<TransportMessage>,
+    server_messages: ServerMessagesSlot,
 }
 
 #[async_trait::async_trait]
@@ -225,6 +219,10 @@ impl TransportHandle for SseTransportHandle {
     async fn send(&self, message: JsonRpcMessage) -> Result<JsonRpcMessage, Error> {
         send_message(&self.sender, message).await
     }
+
+    fn take_server_messages(&self) -> Option<ServerMessages> {
+        self.server_messages.take()
+    }
 }
 
 #[derive(Clone)]
@@ -274,6 +272,7 @@ impl Transport for SseTransport {
 
         // Create a channel for outgoing TransportMessages
         let (tx, rx) = mpsc::channel(32);
+        let (server_tx, server_rx) = mpsc::unbounded_channel();
 
         let post_endpoint: Arc<RwLock<Option<String>>> = Arc::new(RwLock::new(None));
         let post_endpoint_clone = Arc::clone(&post_endpoint);
@@ -282,6 +281,7 @@ impl Transport for SseTransport {
         let actor = SseActor::new(
             rx,
             Arc::new(PendingRequests::new()),
+            server_tx,
             self.sse_url.clone(),
             post_endpoint,
         );
@@ -296,7 +296,10 @@ impl Transport for SseTransport {
         )
         .await
         {
-            Ok(_) => Ok(SseTransportHandle { sender: tx }),
+            Ok(_) => Ok(SseTransportHandle {
+                sender: tx,
+                server_messages: ServerMessagesSlot::new(server_rx),
+            }),
             Err(e) => Err(Error::SseConnection(e.to_string())),
         }
     }
@@ -7,7 +7,10 @@ use mcp_core::protocol::JsonRpcMessage;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::sync::{mpsc, Mutex};
 
-use super::{send_message, Error, PendingRequests, Transport, TransportHandle, TransportMessage};
+use super::{
+    route_incoming_message, send_message, Error, PendingRequests, ServerMessages,
+    ServerMessagesSlot, Transport, TransportHandle, TransportMessage,
+};
 
 /// A `StdioTransport` uses a child process's stdin/stdout as a communication channel.
 ///
@@ -15,6 +18,7 @@ use super::{send_message, Error, PendingRequests, Transport, TransportHandle, Tr
 pub struct StdioActor {
     receiver: mpsc::Receiver<TransportMessage>,
     pending_requests: Arc<PendingRequests>,
+    server_messages: mpsc::UnboundedSender<JsonRpcMessage>,
     _process: Child, // we store the process to keep it alive
     error_sender: mpsc::Sender<Error>,
     stdin: ChildStdin,
@@ -26,7 +30,11 @@ impl StdioActor {
     pub async fn run(mut self) {
         use tokio::pin;
 
-        let incoming = Self::handle_incoming_messages(self.stdout, self.pending_requests.clone());
+        let incoming = Self::handle_incoming_messages(
+            self.stdout,
+            self.pending_requests.clone(),
+            self.server_messages,
+        );
         let outgoing = Self::handle_outgoing_messages(
             self.receiver,
             self.stdin,
@@ -71,7 +79,11 @@ impl StdioActor {
         self.pending_requests.clear().await;
     }
 
-    async fn handle_incoming_messages(stdout: ChildStdout, pending_requests: Arc<PendingRequests>) {
+    async fn handle_incoming_messages(
+        stdout: ChildStdout,
+        pending_requests: Arc<PendingRequests>,
+        server_messages: mpsc::UnboundedSender<JsonRpcMessage>,
+    ) {
         let mut reader = BufReader::new(stdout);
         let mut line = String::new();
         loop {
@@ -87,19 +99,7 @@ impl StdioActor {
                             "Received incoming message"
                         );
 
-                        match &message {
-                            JsonRpcMessage::Response(response) => {
-                                if let Some(id) = &response.id {
-                                    pending_requests.respond(&id.to_string(), Ok(message)).await;
-                                }
-                            }
-                            JsonRpcMessage::Error(error) => {
-                                if let Some(id) = &error.id {
-                                    pending_requests.respond(&id.to_string(), Ok(message)).await;
-                                }
-                            }
-                            _ => {} // TODO: Handle other variants (Request, etc.)
-                        }
+                        route_incoming_message(&pending_requests, &server_messages, message).await;
                     }
                     line.clear();
                 }
@@ -130,11 +130,9 @@ impl StdioActor {
             tracing::debug!(message = ?transport_msg.message, "Sending outgoing message");
 
             if let Some(response_tx) = transport_msg.response_tx.take() {
-                if let JsonRpcMessage::Request(request) = &transport_msg.message {
-                    if let Some(id) = &request.id {
-                        pending_requests.insert(id.to_string(), response_tx).await;
-                    }
-                }
+                pending_requests
+                    .insert_message(&transport_msg.message, response_tx)
+                    .await;
             }
 
             if let Err(e) = stdin
@@ -159,6 +157,7 @@ impl StdioActor {
 pub struct StdioTransportHandle {
     sender: mpsc::Sender<TransportMessage>,
     error_receiver: Arc<Mutex<mpsc::Receiver<Error>>>,
+    server_messages: ServerMessagesSlot,
 }
 
 #[async_trait::async_trait]
@@ -169,6 +168,10 @@ impl TransportHandle for StdioTransportHandle {
         self.check_for_errors().await?;
         result
     }
+
+    fn take_server_messages(&self) -> Option<ServerMessages> {
+        self.server_messages.take()
+    }
 }
 
 impl StdioTransportHandle {
@@ -252,10 +255,12 @@ impl Transport for StdioTransport {
         let (process, stdin, stdout, stderr) = self.spawn_process().await?;
         let (message_tx, message_rx) = mpsc::channel(32);
         let (error_tx, error_rx) = mpsc::channel(1);
+        let (server_tx, server_rx) = mpsc::unbounded_channel();
 
         let actor = StdioActor {
             receiver: message_rx,
             pending_requests: Arc::new(PendingRequests::new()),
+            server_messages: server_tx,
             _process: process,
             error_sender: error_tx,
             stdin,
@@ -268,6 +273,7 @@ impl Transport for StdioTransport {
         let handle = StdioTransportHandle {
             sender: message_tx,
             error_receiver: Arc::new(Mutex::new(error_rx)),
+            server_messages: ServerMessagesSlot::new(server_rx),
         };
         Ok(handle)
     }
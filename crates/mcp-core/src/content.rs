@@ -2,6 +2,7 @@
 /// The various content types can be display to humans but also understood by models
 /// They include optional annotations used to help inform agent usage
 use super::role::Role;
+use crate::meta::Meta;
 use crate::resource::ResourceContents;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -39,6 +40,8 @@ pub struct TextContent {
     pub text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub annotations: Option<Annotations>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -48,6 +51,60 @@ pub struct ImageContent {
     pub mime_type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub annotations: Option<Annotations>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioContent {
+    /// The base64-encoded audio data
+    pub data: String,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// A link to a resource the client can read with `resources/read`, rather than embedding
+/// its contents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLink {
+    pub uri: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl ResourceLink {
+    pub fn new<U: Into<String>, N: Into<String>>(uri: U, name: N) -> Self {
+        Self {
+            uri: uri.into(),
+            name: name.into(),
+            description: None,
+            mime_type: None,
+            annotations: None,
+            meta: None,
+        }
+    }
+
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_mime_type<S: Into<String>>(mut self, mime_type: S) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -56,6 +113,8 @@ pub struct EmbeddedResource {
     pub resource: ResourceContents,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub annotations: Option<Annotations>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 impl EmbeddedResource {
@@ -72,7 +131,10 @@ impl EmbeddedResource {
 pub enum Content {
     Text(TextContent),
     Image(ImageContent),
+    Audio(AudioContent),
     Resource(EmbeddedResource),
+    #[serde(rename = "resource_link")]
+    ResourceLink(ResourceLink),
 }
 
 impl Content {
@@ -80,6 +142,7 @@ impl Content {
         Content::Text(TextContent {
             text: text.into(),
             annotations: None,
+            meta: None,
         })
     }
 
@@ -88,13 +151,28 @@ impl Content {
             data: data.into(),
             mime_type: mime_type.into(),
             annotations: None,
+            meta: None,
+        })
+    }
+
+    pub fn audio<S: Into<String>, T: Into<String>>(data: S, mime_type: T) -> Self {
+        Content::Audio(AudioContent {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            annotations: None,
+            meta: None,
         })
     }
 
+    pub fn resource_link(link: ResourceLink) -> Self {
+        Content::ResourceLink(link)
+    }
+
     pub fn resource(resource: ResourceContents) -> Self {
         Content::Resource(EmbeddedResource {
             resource,
             annotations: None,
+            meta: None,
         })
     }
 
@@ -106,6 +184,7 @@ impl Content {
                 text: content.into(),
             },
             annotations: None,
+            meta: None,
         })
     }
 
@@ -125,13 +204,45 @@ impl Content {
         }
     }
 
-    /// Set the audience for the content
-    pub fn with_audience(mut self, audience: Vec<Role>) -> Self {
-        let annotations = match &mut self {
+    /// Get the audio content if this is an AudioContent variant
+    pub fn as_audio(&self) -> Option<(&str, &str)> {
+        match self {
+            Content::Audio(audio) => Some((&audio.data, &audio.mime_type)),
+            _ => None,
+        }
+    }
+
+    /// Get the link if this is a ResourceLink variant
+    pub fn as_resource_link(&self) -> Option<&ResourceLink> {
+        match self {
+            Content::ResourceLink(link) => Some(link),
+            _ => None,
+        }
+    }
+
+    fn annotations(&self) -> Option<&Annotations> {
+        match self {
+            Content::Text(text) => text.annotations.as_ref(),
+            Content::Image(image) => image.annotations.as_ref(),
+            Content::Audio(audio) => audio.annotations.as_ref(),
+            Content::Resource(resource) => resource.annotations.as_ref(),
+            Content::ResourceLink(link) => link.annotations.as_ref(),
+        }
+    }
+
+    fn annotations_mut(&mut self) -> &mut Option<Annotations> {
+        match self {
             Content::Text(text) => &mut text.annotations,
             Content::Image(image) => &mut image.annotations,
+            Content::Audio(audio) => &mut audio.annotations,
             Content::Resource(resource) => &mut resource.annotations,
-        };
+            Content::ResourceLink(link) => &mut link.annotations,
+        }
+    }
+
+    /// Set the audience for the content
+    pub fn with_audience(mut self, audience: Vec<Role>) -> Self {
+        let annotations = self.annotations_mut();
         *annotations = Some(match annotations.take() {
             Some(mut a) => {
                 a.audience = Some(audience);
@@ -153,11 +264,7 @@ impl Content {
         if !(0.0..=1.0).contains(&priority) {
             panic!("Priority must be between 0.0 and 1.0");
         }
-        let annotations = match &mut self {
-            Content::Text(text) => &mut text.annotations,
-            Content::Image(image) => &mut image.annotations,
-            Content::Resource(resource) => &mut resource.annotations,
-        };
+        let annotations = self.annotations_mut();
         *annotations = Some(match annotations.take() {
             Some(mut a) => {
                 a.priority = Some(priority);
@@ -174,30 +281,24 @@ impl Content {
 
     /// Get the audience if set
     pub fn audience(&self) -> Option<&Vec<Role>> {
-        match self {
-            Content::Text(text) => text.annotations.as_ref().and_then(|a| a.audience.as_ref()),
-            Content::Image(image) => image.annotations.as_ref().and_then(|a| a.audience.as_ref()),
-            Content::Resource(resource) => resource
-                .annotations
-                .as_ref()
-                .and_then(|a| a.audience.as_ref()),
-        }
+        self.annotations().and_then(|a| a.audience.as_ref())
     }
 
     /// Get the priority if set
     pub fn priority(&self) -> Option<f32> {
-        match self {
-            Content::Text(text) => text.annotations.as_ref().and_then(|a| a.priority),
-            Content::Image(image) => image.annotations.as_ref().and_then(|a| a.priority),
-            Content::Resource(resource) => resource.annotations.as_ref().and_then(|a| a.priority),
-        }
+        self.annotations().and_then(|a| a.priority)
     }
 
     pub fn unannotated(&self) -> Self {
         match self {
             Content::Text(text) => Content::text(text.text.clone()),
             Content::Image(image) => Content::image(image.data.clone(), image.mime_type.clone()),
+            Content::Audio(audio) => Content::audio(audio.data.clone(), audio.mime_type.clone()),
             Content::Resource(resource) => Content::resource(resource.resource.clone()),
+            Content::ResourceLink(link) => Content::resource_link(ResourceLink {
+                annotations: None,
+                ..link.clone()
+            }),
         }
     }
 }
@@ -220,6 +321,29 @@ mod tests {
         assert_eq!(content.as_image(), Some(("data", "image/png")));
     }
 
+    #[test]
+    fn test_content_audio_and_resource_link() {
+        let audio = Content::audio("data", "audio/wav").with_priority(0.5);
+        assert_eq!(audio.as_audio(), Some(("data", "audio/wav")));
+        assert_eq!(audio.priority(), Some(0.5));
+        assert_eq!(audio.unannotated().priority(), None);
+
+        let link = Content::resource_link(
+            ResourceLink::new("file:///notes.md", "notes").with_mime_type("text/markdown"),
+        )
+        .with_audience(vec![Role::User]);
+        assert_eq!(link.audience(), Some(&vec![Role::User]));
+        assert_eq!(
+            serde_json::to_value(link.unannotated()).unwrap(),
+            serde_json::json!({
+                "type": "resource_link",
+                "uri": "file:///notes.md",
+                "name": "notes",
+                "mimeType": "text/markdown"
+            })
+        );
+    }
+
     #[test]
     fn test_content_annotations_basic() {
         let content = Content::text("hello")
@@ -0,0 +1,346 @@
+/// Elicitation lets servers ask the user for input through the client, with
+/// `elicitation/create`
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::meta::Meta;
+
+/// The params of an `elicitation/create` request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElicitRequest {
+    /// The message shown to the user, explaining what is asked and why
+    pub message: String,
+    /// The form the user fills in
+    pub requested_schema: ElicitationSchema,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl ElicitRequest {
+    pub const METHOD: &'static str = "elicitation/create";
+
+    pub fn new<S: Into<String>>(message: S, requested_schema: ElicitationSchema) -> Self {
+        Self {
+            message: message.into(),
+            requested_schema,
+            meta: None,
+        }
+    }
+}
+
+/// A restricted JSON schema describing a form: a flat object whose properties are all
+/// primitive values, so that any client can render it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElicitationSchema {
+    /// Always "object"
+    #[serde(rename = "type")]
+    pub schema_type: String,
+    pub properties: BTreeMap<String, PrimitiveSchema>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+}
+
+impl Default for ElicitationSchema {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ElicitationSchema {
+    /// A form without any fields, as used to ask for a plain confirmation
+    pub fn new() -> Self {
+        Self {
+            schema_type: "object".to_string(),
+            properties: BTreeMap::new(),
+            required: Vec::new(),
+        }
+    }
+
+    /// Add a field the user may leave empty
+    pub fn optional<S: Into<String>>(mut self, name: S, schema: PrimitiveSchema) -> Self {
+        self.properties.insert(name.into(), schema);
+        self
+    }
+
+    /// Add a field the user must fill in
+    pub fn required<S: Into<String>>(mut self, name: S, schema: PrimitiveSchema) -> Self {
+        let name = name.into();
+        self.required.push(name.clone());
+        self.properties.insert(name, schema);
+        self
+    }
+
+    /// Check that the content the user submitted fills in the form
+    pub fn validate(&self, content: &Map<String, Value>) -> Result<(), String> {
+        for name in &self.required {
+            if !content.contains_key(name) {
+                return Err(format!("Missing required field '{}'", name));
+            }
+        }
+        for (name, value) in content {
+            let schema = self
+                .properties
+                .get(name)
+                .ok_or_else(|| format!("Unknown field '{}'", name))?;
+            schema
+                .validate(value)
+                .map_err(|e| format!("Invalid field '{}': {}", name, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// The schema of a single form field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PrimitiveSchema {
+    String(StringSchema),
+    Number(NumberSchema),
+    Integer(NumberSchema),
+    Boolean(BooleanSchema),
+}
+
+impl PrimitiveSchema {
+    pub fn string<S: Into<String>>(title: S) -> Self {
+        PrimitiveSchema::String(StringSchema {
+            title: Some(title.into()),
+            ..Default::default()
+        })
+    }
+
+    /// A string restricted to `values`
+    pub fn enumeration<S: Into<String>>(title: S, values: Vec<String>) -> Self {
+        PrimitiveSchema::String(StringSchema {
+            title: Some(title.into()),
+            enum_values: Some(values),
+            ..Default::default()
+        })
+    }
+
+    pub fn number<S: Into<String>>(title: S) -> Self {
+        PrimitiveSchema::Number(NumberSchema {
+            title: Some(title.into()),
+            ..Default::default()
+        })
+    }
+
+    pub fn integer<S: Into<String>>(title: S) -> Self {
+        PrimitiveSchema::Integer(NumberSchema {
+            title: Some(title.into()),
+            ..Default::default()
+        })
+    }
+
+    pub fn boolean<S: Into<String>>(title: S) -> Self {
+        PrimitiveSchema::Boolean(BooleanSchema {
+            title: Some(title.into()),
+            ..Default::default()
+        })
+    }
+
+    fn validate(&self, value: &Value) -> Result<(), String> {
+        match (self, value) {
+            (PrimitiveSchema::String(schema), Value::String(text)) => schema.validate(text),
+            (PrimitiveSchema::Number(schema), Value::Number(number)) => {
+                schema.validate(number.as_f64().unwrap_or_default())
+            }
+            (PrimitiveSchema::Integer(schema), Value::Number(number))
+                if number.is_i64() || number.is_u64() =>
+            {
+                schema.validate(number.as_f64().unwrap_or_default())
+            }
+            (PrimitiveSchema::Boolean(_), Value::Bool(_)) => Ok(()),
+            (schema, value) => Err(format!("expected {}, got {}", schema.type_name(), value)),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            PrimitiveSchema::String(_) => "a string",
+            PrimitiveSchema::Number(_) => "a number",
+            PrimitiveSchema::Integer(_) => "an integer",
+            PrimitiveSchema::Boolean(_) => "a boolean",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StringSchema {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// One of "email", "uri", "date" or "date-time"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// The only values allowed
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    pub enum_values: Option<Vec<String>>,
+    /// Names to show for the allowed values, in the same order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enum_names: Option<Vec<String>>,
+}
+
+impl StringSchema {
+    fn validate(&self, text: &str) -> Result<(), String> {
+        let length = text.chars().count();
+        if self.min_length.is_some_and(|min| length < min) {
+            return Err("too short".to_string());
+        }
+        if self.max_length.is_some_and(|max| length > max) {
+            return Err("too long".to_string());
+        }
+        if let Some(values) = &self.enum_values {
+            if !values.iter().any(|value| value == text) {
+                return Err(format!("'{}' is not one of the allowed values", text));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct NumberSchema {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+}
+
+impl NumberSchema {
+    fn validate(&self, number: f64) -> Result<(), String> {
+        if self.minimum.is_some_and(|min| number < min) {
+            return Err(format!("{} is below the minimum", number));
+        }
+        if self.maximum.is_some_and(|max| number > max) {
+            return Err(format!("{} is above the maximum", number));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct BooleanSchema {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<bool>,
+}
+
+/// How the user responded to an elicitation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElicitAction {
+    /// The user submitted the form
+    Accept,
+    /// The user explicitly refused to provide the input
+    Decline,
+    /// The user dismissed the request without choosing
+    Cancel,
+}
+
+/// The result of an `elicitation/create` request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElicitResult {
+    pub action: ElicitAction,
+    /// The submitted form, present when the user accepted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<Map<String, Value>>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl ElicitResult {
+    /// The user submitted `content`
+    pub fn accept(content: Map<String, Value>) -> Self {
+        Self {
+            action: ElicitAction::Accept,
+            content: Some(content),
+            meta: None,
+        }
+    }
+
+    pub fn decline() -> Self {
+        Self {
+            action: ElicitAction::Decline,
+            content: None,
+            meta: None,
+        }
+    }
+
+    pub fn cancel() -> Self {
+        Self {
+            action: ElicitAction::Cancel,
+            content: None,
+            meta: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn deploy_form() -> ElicitationSchema {
+        ElicitationSchema::new()
+            .required(
+                "environment",
+                PrimitiveSchema::enumeration(
+                    "Environment",
+                    vec!["staging".to_string(), "production".to_string()],
+                ),
+            )
+            .optional("replicas", PrimitiveSchema::integer("Replicas"))
+    }
+
+    #[test]
+    fn test_schema_serialization() {
+        assert_eq!(
+            serde_json::to_value(deploy_form()).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "environment": {
+                        "type": "string",
+                        "title": "Environment",
+                        "enum": ["staging", "production"]
+                    },
+                    "replicas": {"type": "integer", "title": "Replicas"}
+                },
+                "required": ["environment"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_content() {
+        let form = deploy_form();
+        let content = |value: Value| value.as_object().unwrap().clone();
+
+        assert!(form
+            .validate(&content(json!({"environment": "staging", "replicas": 3})))
+            .is_ok());
+        assert!(form.validate(&content(json!({"replicas": 3}))).is_err());
+        assert!(form
+            .validate(&content(json!({"environment": "qa"})))
+            .is_err());
+        assert!(form
+            .validate(&content(json!({"environment": "staging", "replicas": 1.5})))
+            .is_err());
+    }
+}
@@ -4,6 +4,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
 
+use crate::resource::ResourceTemplate;
+use crate::tool::{Tool, ToolAnnotations};
+
 #[non_exhaustive]
 #[derive(Error, Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub enum ToolError {
@@ -46,24 +49,82 @@ pub trait ToolHandler: Send + Sync + 'static {
     /// A description of what the tool does
     fn description(&self) -> &'static str;
 
+    /// A human readable name for the tool
+    fn title(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Hints about how the tool behaves, such as whether it only reads
+    fn annotations(&self) -> Option<ToolAnnotations> {
+        None
+    }
+
     /// JSON schema describing the tool's parameters
     fn schema(&self) -> Value;
 
+    /// JSON schema describing the object the tool returns, for tools with structured output
+    fn output_schema(&self) -> Option<Value> {
+        None
+    }
+
     /// Execute the tool with the given parameters
     async fn call(&self, params: Value) -> ToolResult<Value>;
+
+    /// Describe the tool for `tools/list`
+    fn tool(&self) -> Tool {
+        Tool {
+            name: self.name().to_string(),
+            title: self.title().map(str::to_string),
+            description: self.description().to_string(),
+            input_schema: self.schema(),
+            output_schema: self.output_schema(),
+            annotations: self.annotations(),
+        }
+    }
 }
 
-/// Trait for implementing MCP resources
+/// Trait for implementing MCP resource templates
 #[async_trait]
 pub trait ResourceTemplateHandler: Send + Sync + 'static {
-    /// The URL template for this resource
-    fn template() -> &'static str;
+    /// The RFC 6570 URI template of the resources this handler serves
+    fn template(&self) -> &'static str;
+
+    /// The name of the kind of resource
+    fn name(&self) -> &'static str;
+
+    /// A description of the resources
+    fn description(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The MIME type of the resources, if they share one
+    fn mime_type(&self) -> Option<&'static str> {
+        None
+    }
 
     /// JSON schema describing the resource parameters
-    fn schema() -> Value;
+    fn schema(&self) -> Value;
 
-    /// Get the resource value
+    /// Get the resource value. `params` is an object holding the values of the template
+    /// variables found in the requested URI.
     async fn get(&self, params: Value) -> ToolResult<String>;
+
+    /// Suggest values for the template variable `variable`, starting with `value`. Only
+    /// used by routers that advertise completions.
+    async fn complete(&self, _variable: &str, _value: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Describe the template for `resources/templates/list`
+    fn resource_template(&self) -> ResourceTemplate {
+        ResourceTemplate {
+            uri_template: self.template().to_string(),
+            name: self.name().to_string(),
+            description: self.description().map(str::to_string),
+            mime_type: self.mime_type().map(str::to_string),
+            annotations: None,
+        }
+    }
 }
 
 /// Helper function to generate JSON schema for a type
@@ -71,3 +132,16 @@ pub fn generate_schema<T: JsonSchema>() -> ToolResult<Value> {
     let schema = schemars::schema_for!(T);
     serde_json::to_value(schema).map_err(|e| ToolError::SchemaError(e.to_string()))
 }
+
+/// Generate the output schema of a tool returning `T`. Structured content must be an
+/// object, so this fails for types that don't serialize to one.
+pub fn generate_output_schema<T: JsonSchema>() -> ToolResult<Value> {
+    let schema = generate_schema::<T>()?;
+    if schema.get("type").and_then(Value::as_str) != Some("object") {
+        return Err(ToolError::SchemaError(format!(
+            "Output schema of {} must describe an object",
+            std::any::type_name::<T>()
+        )));
+    }
+    Ok(schema)
+}
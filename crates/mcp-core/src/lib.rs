@@ -1,12 +1,22 @@
 pub mod content;
-pub use content::{Annotations, Content, ImageContent, TextContent};
+pub mod elicitation;
+pub use content::{Annotations, AudioContent, Content, ImageContent, ResourceLink, TextContent};
 pub mod handler;
+pub mod meta;
+pub use meta::Meta;
 pub mod role;
 pub use role::Role;
 pub mod tool;
-pub use tool::{Tool, ToolCall};
+pub use tool::{Tool, ToolAnnotations, ToolCall};
 pub mod resource;
-pub use resource::{Resource, ResourceContents};
+pub use resource::{Resource, ResourceContents, ResourceTemplate};
+pub mod uri_template;
+pub use uri_template::UriTemplate;
 pub mod protocol;
+pub mod version;
 pub use handler::{ToolError, ToolResult};
+pub use version::ProtocolVersion;
 pub mod prompt;
+pub mod sampling;
+pub mod template;
+pub use sampling::{CreateMessageRequest, CreateMessageResult, ModelPreferences, SamplingMessage};
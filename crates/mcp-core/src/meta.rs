@@ -0,0 +1,92 @@
+/// The `_meta` map that requests, results, notifications and content may carry
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::protocol::ProgressToken;
+
+/// Metadata attached to a protocol message under `_meta`.
+///
+/// The protocol reserves some keys, like `progressToken` on requests, which have typed
+/// accessors. Any other key is kept as is, so trace context or vendor extensions
+/// (conventionally prefixed, e.g. `example.com/trace`) survive a round trip.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Meta(pub Map<String, Value>);
+
+impl Meta {
+    pub const PROGRESS_TOKEN: &'static str = "progressToken";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.get(key)
+    }
+
+    /// Set `key`, returning the previous value if there was one
+    pub fn insert<K: Into<String>, V: Into<Value>>(&mut self, key: K, value: V) -> Option<Value> {
+        self.0.insert(key.into(), value.into())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.0.remove(key)
+    }
+
+    /// Set `key` and return self, for building metadata inline
+    pub fn with<K: Into<String>, V: Into<Value>>(mut self, key: K, value: V) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.0.iter()
+    }
+
+    /// The token asking the receiver of a request to report its progress
+    pub fn progress_token(&self) -> Option<ProgressToken> {
+        serde_json::from_value(self.get(Self::PROGRESS_TOKEN)?.clone()).ok()
+    }
+
+    pub fn with_progress_token(self, token: ProgressToken) -> Self {
+        let token = serde_json::to_value(token).expect("progress tokens serialize to JSON");
+        self.with(Self::PROGRESS_TOKEN, token)
+    }
+
+    /// Add the keys of `other`, overwriting the ones both have
+    pub fn extend(&mut self, other: Meta) {
+        self.0.extend(other.0);
+    }
+}
+
+impl From<Map<String, Value>> for Meta {
+    fn from(map: Map<String, Value>) -> Self {
+        Self(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_meta_round_trip() {
+        let meta = Meta::new()
+            .with_progress_token(ProgressToken::Number(3.into()))
+            .with("example.com/trace", "abc");
+        let value = serde_json::to_value(&meta).unwrap();
+        assert_eq!(
+            value,
+            json!({"progressToken": 3, "example.com/trace": "abc"})
+        );
+
+        let meta: Meta = serde_json::from_value(value).unwrap();
+        assert_eq!(meta.progress_token(), Some(ProgressToken::Number(3.into())));
+        assert_eq!(meta.get("example.com/trace"), Some(&json!("abc")));
+    }
+}
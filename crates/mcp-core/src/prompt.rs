@@ -1,8 +1,11 @@
-use crate::content::{Annotations, EmbeddedResource, ImageContent};
+use crate::content::{Annotations, AudioContent, EmbeddedResource, ImageContent, ResourceLink};
 use crate::handler::PromptError;
 use crate::resource::ResourceContents;
+use crate::template::{Template, TemplateError};
 use base64::engine::{general_purpose::STANDARD as BASE64_STANDARD, Engine};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::{Arc, OnceLock};
 
 /// A prompt that can be used to generate text from a model
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -66,8 +69,13 @@ pub enum PromptMessageContent {
     Text { text: String },
     /// Image content with base64-encoded data
     Image { image: ImageContent },
+    /// Audio content with base64-encoded data
+    Audio { audio: AudioContent },
     /// Embedded server-side resource
     Resource { resource: EmbeddedResource },
+    /// Link to a server-side resource
+    #[serde(rename = "resource_link")]
+    ResourceLink { resource_link: ResourceLink },
 }
 
 /// A message in a prompt conversation
@@ -116,11 +124,54 @@ impl PromptMessage {
                     data,
                     mime_type,
                     annotations,
+                    meta: None,
                 },
             },
         })
     }
 
+    pub fn new_audio<S: Into<String>>(
+        role: PromptMessageRole,
+        data: S,
+        mime_type: S,
+        annotations: Option<Annotations>,
+    ) -> Result<Self, PromptError> {
+        let data = data.into();
+        let mime_type = mime_type.into();
+
+        // Validate base64 data
+        BASE64_STANDARD.decode(&data).map_err(|_| {
+            PromptError::InvalidParameters("Audio data must be valid base64".to_string())
+        })?;
+
+        // Validate mime type
+        if !mime_type.starts_with("audio/") {
+            return Err(PromptError::InvalidParameters(
+                "MIME type must be a valid audio type (e.g. audio/wav)".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            role,
+            content: PromptMessageContent::Audio {
+                audio: AudioContent {
+                    data,
+                    mime_type,
+                    annotations,
+                    meta: None,
+                },
+            },
+        })
+    }
+
+    /// Create a new message linking to a resource
+    pub fn new_resource_link(role: PromptMessageRole, resource_link: ResourceLink) -> Self {
+        Self {
+            role,
+            content: PromptMessageContent::ResourceLink { resource_link },
+        }
+    }
+
     /// Create a new resource message
     pub fn new_resource(
         role: PromptMessageRole,
@@ -141,24 +192,338 @@ impl PromptMessage {
                 resource: EmbeddedResource {
                     resource: resource_contents,
                     annotations,
+                    meta: None,
                 },
             },
         }
     }
 }
 
-/// A template for a prompt
-#[derive(Debug, Serialize, Deserialize)]
+/// A template for a prompt, rendered into the text of a user message with the arguments
+/// of `prompts/get`. See [`Template`] for the syntax.
+///
+/// The template is compiled once, the first time it is needed, and clones share the
+/// compiled template, so a router can hand out clones of the templates it keeps.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PromptTemplate {
+    /// The name of the prompt
     pub id: String,
-    pub template: String,
-    pub arguments: Vec<PromptArgumentTemplate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    template: String,
+    #[serde(default)]
+    arguments: Vec<PromptArgumentTemplate>,
+    #[serde(skip)]
+    compiled: CompiledTemplate,
+}
+
+/// The compiled form of a [`PromptTemplate`], shared by its clones
+#[derive(Clone, Default)]
+struct CompiledTemplate(Arc<OnceLock<Result<Template, TemplateError>>>);
+
+// Whether a template was compiled yet doesn't change what it is
+impl PartialEq for CompiledTemplate {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl std::fmt::Debug for CompiledTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.get().is_some() {
+            "Compiled"
+        } else {
+            "NotCompiled"
+        })
+    }
+}
+
+impl PromptTemplate {
+    pub fn new<I: Into<String>, T: Into<String>>(id: I, template: T) -> Self {
+        Self {
+            id: id.into(),
+            description: None,
+            template: template.into(),
+            arguments: Vec::new(),
+            compiled: CompiledTemplate::default(),
+        }
+    }
+
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_argument(mut self, argument: PromptArgumentTemplate) -> Self {
+        self.arguments.push(argument);
+        // The declared arguments decide whether the template is valid
+        self.compiled = CompiledTemplate::default();
+        self
+    }
+
+    /// The source text of the template
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    pub fn arguments(&self) -> &[PromptArgumentTemplate] {
+        &self.arguments
+    }
+
+    /// Parse the template, checking that every placeholder is a declared argument. The
+    /// result is kept, so only the first call parses.
+    pub fn compile(&self) -> Result<&Template, TemplateError> {
+        self.compiled
+            .0
+            .get_or_init(|| {
+                let template = Template::parse(&self.template)?;
+                if let Some(undeclared) = template
+                    .variables()
+                    .into_iter()
+                    .find(|variable| !self.arguments.iter().any(|arg| &arg.name == variable))
+                {
+                    return Err(TemplateError::UndeclaredArgument(undeclared));
+                }
+                Ok(template)
+            })
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    /// Render the template with `arguments`, converting each one to its declared type.
+    /// Clients send arguments as strings, so e.g. "3" is accepted for an integer.
+    pub fn render(&self, arguments: &Map<String, Value>) -> Result<String, TemplateError> {
+        let template = self.compile()?;
+
+        if let Some(unknown) = arguments
+            .keys()
+            .find(|name| !self.arguments.iter().any(|arg| &arg.name == *name))
+        {
+            return Err(TemplateError::UnknownArgument(unknown.clone()));
+        }
+        let mut values = Map::new();
+        for argument in &self.arguments {
+            match arguments.get(&argument.name) {
+                None | Some(Value::Null) => {}
+                Some(Value::String(text)) if text.is_empty() => {}
+                Some(value) => {
+                    let value = argument.argument_type.coerce(value).map_err(|message| {
+                        TemplateError::InvalidArgument {
+                            name: argument.name.clone(),
+                            message,
+                        }
+                    })?;
+                    values.insert(argument.name.clone(), value);
+                    continue;
+                }
+            }
+            if argument.required == Some(true) {
+                return Err(TemplateError::MissingArgument(argument.name.clone()));
+            }
+        }
+
+        template.render(&values)
+    }
+
+    /// The prompt as listed by `prompts/list`
+    pub fn prompt(&self) -> Prompt {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|argument| PromptArgument {
+                name: argument.name.clone(),
+                description: argument.description.clone(),
+                required: argument.required,
+            })
+            .collect::<Vec<_>>();
+        Prompt {
+            name: self.id.clone(),
+            description: self.description.clone(),
+            arguments: (!arguments.is_empty()).then_some(arguments),
+        }
+    }
 }
 
-/// A template for a prompt argument, this should be identical to PromptArgument
-#[derive(Debug, Serialize, Deserialize)]
+/// A template for a prompt argument, a [`PromptArgument`] with a type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PromptArgumentTemplate {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub required: Option<bool>,
+    #[serde(rename = "type", default)]
+    pub argument_type: ArgumentType,
+}
+
+impl PromptArgumentTemplate {
+    /// An optional string argument
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            required: None,
+            argument_type: ArgumentType::default(),
+        }
+    }
+
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_type(mut self, argument_type: ArgumentType) -> Self {
+        self.argument_type = argument_type;
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = Some(true);
+        self
+    }
+}
+
+/// The type of the value of a prompt argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArgumentType {
+    #[default]
+    String,
+    Number,
+    Integer,
+    Boolean,
+    /// A JSON array, for `{#each}` loops
+    Array,
+    /// A JSON object, whose fields are used as `{argument.field}`
+    Object,
+}
+
+impl ArgumentType {
+    /// Convert `value` to this type, parsing it if it is a string
+    fn coerce(self, value: &Value) -> Result<Value, String> {
+        let parsed = match (self, value) {
+            (ArgumentType::String, Value::String(_))
+            | (ArgumentType::Number, Value::Number(_))
+            | (ArgumentType::Boolean, Value::Bool(_))
+            | (ArgumentType::Array, Value::Array(_))
+            | (ArgumentType::Object, Value::Object(_)) => Some(value.clone()),
+            (ArgumentType::String, Value::Number(_) | Value::Bool(_)) => {
+                Some(Value::String(value.to_string()))
+            }
+            (ArgumentType::Integer, Value::Number(number)) => {
+                (number.is_i64() || number.is_u64()).then(|| value.clone())
+            }
+            (ArgumentType::Number, Value::String(text)) => text
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number),
+            (ArgumentType::Integer, Value::String(text)) => {
+                text.trim().parse::<i64>().ok().map(Value::from)
+            }
+            (ArgumentType::Boolean, Value::String(text)) => match text.trim() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            (ArgumentType::Array, Value::String(text)) => {
+                serde_json::from_str(text).ok().filter(Value::is_array)
+            }
+            (ArgumentType::Object, Value::String(text)) => {
+                serde_json::from_str(text).ok().filter(Value::is_object)
+            }
+            _ => None,
+        };
+        parsed.ok_or_else(|| format!("expected {}, got {}", self.description(), value))
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            ArgumentType::String => "a string",
+            ArgumentType::Number => "a number",
+            ArgumentType::Integer => "an integer",
+            ArgumentType::Boolean => "true or false",
+            ArgumentType::Array => "a JSON array",
+            ArgumentType::Object => "a JSON object",
+        }
+    }
+}
+
+impl From<TemplateError> for PromptError {
+    fn from(error: TemplateError) -> Self {
+        if error.is_argument_error() {
+            PromptError::InvalidParameters(error.to_string())
+        } else {
+            PromptError::InternalError(error.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn report_template() -> PromptTemplate {
+        PromptTemplate::new(
+            "report",
+            "Summarize {#if days}the last {days} days of {/if}{service} logs.\n\
+             {#each focus as topic}- {topic}\n{/each}",
+        )
+        .with_argument(PromptArgumentTemplate::new("service").required())
+        .with_argument(PromptArgumentTemplate::new("days").with_type(ArgumentType::Integer))
+        .with_argument(PromptArgumentTemplate::new("focus").with_type(ArgumentType::Array))
+    }
+
+    #[test]
+    fn test_render_typed_arguments() {
+        let arguments = json!({"service": "api", "days": "7", "focus": "[\"errors\"]"});
+        assert_eq!(
+            report_template()
+                .render(arguments.as_object().unwrap())
+                .unwrap(),
+            "Summarize the last 7 days of api logs.\n- errors\n"
+        );
+
+        let invalid = |arguments: Value| {
+            report_template()
+                .render(arguments.as_object().unwrap())
+                .unwrap_err()
+        };
+        assert_eq!(
+            invalid(json!({"days": "7"})),
+            TemplateError::MissingArgument("service".to_string())
+        );
+        assert!(matches!(
+            invalid(json!({"service": "api", "days": "a week"})),
+            TemplateError::InvalidArgument { .. }
+        ));
+        assert_eq!(
+            invalid(json!({"service": "api", "level": "warn"})),
+            TemplateError::UnknownArgument("level".to_string())
+        );
+    }
+
+    #[test]
+    fn test_compile_checks_placeholders() {
+        let template = PromptTemplate::new("greet", "Hello {name}");
+        assert_eq!(
+            template.compile(),
+            Err(TemplateError::UndeclaredArgument("name".to_string()))
+        );
+        assert!(template
+            .with_argument(PromptArgumentTemplate::new("name"))
+            .compile()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_clones_share_compiled_template() {
+        let template = report_template();
+        let clone = template.clone();
+        let compiled = template.compile().unwrap();
+        assert!(std::ptr::eq(compiled, clone.compile().unwrap()));
+        assert_eq!(template, report_template());
+    }
 }
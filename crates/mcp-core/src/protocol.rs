@@ -1,29 +1,90 @@
 /// The protocol messages exchanged between client and server
 use crate::{
     content::Content,
+    meta::Meta,
     prompt::{Prompt, PromptMessage},
     resource::Resource,
-    resource::ResourceContents,
+    resource::{ResourceContents, ResourceTemplate},
     tool::Tool,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
+use std::fmt;
+
+/// A JSON-RPC request id, which peers may send as either a number or a string. Any
+/// JSON number is accepted, negative and fractional ones included.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(serde_json::Number),
+    String(String),
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestId::Number(n) => write!(f, "{}", n),
+            RequestId::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<u64> for RequestId {
+    fn from(id: u64) -> Self {
+        RequestId::Number(id.into())
+    }
+}
+
+impl From<i64> for RequestId {
+    fn from(id: i64) -> Self {
+        RequestId::Number(id.into())
+    }
+}
+
+impl From<String> for RequestId {
+    fn from(id: String) -> Self {
+        RequestId::String(id)
+    }
+}
+
+impl From<&str> for RequestId {
+    fn from(id: &str) -> Self {
+        RequestId::String(id.to_string())
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct JsonRpcRequest {
     pub jsonrpc: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub id: Option<u64>,
+    // Always serialized, a request with a null id must not turn into a notification
+    pub id: Option<RequestId>,
     pub method: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub params: Option<Value>,
 }
 
+impl JsonRpcRequest {
+    /// The metadata the sender attached under `params._meta`
+    pub fn meta(&self) -> Option<Meta> {
+        params_meta(self.params.as_ref())
+    }
+
+    /// The progress token the sender attached under `params._meta.progressToken`, asking
+    /// to be notified about the progress of this request
+    pub fn progress_token(&self) -> Option<ProgressToken> {
+        self.meta()?.progress_token()
+    }
+}
+
+fn params_meta(params: Option<&Value>) -> Option<Meta> {
+    serde_json::from_value(params?.get("_meta")?.clone()).ok()
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct JsonRpcResponse {
     pub jsonrpc: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub id: Option<u64>,
+    // Responses always carry an id, which is null when the request id could not be determined
+    pub id: Option<RequestId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -38,29 +99,115 @@ pub struct JsonRpcNotification {
     pub params: Option<Value>,
 }
 
+impl JsonRpcNotification {
+    /// The metadata the sender attached under `params._meta`
+    pub fn meta(&self) -> Option<Meta> {
+        params_meta(self.params.as_ref())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct JsonRpcError {
     pub jsonrpc: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub id: Option<u64>,
+    pub id: Option<RequestId>,
     pub error: ErrorData,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-#[serde(untagged, try_from = "JsonRpcRaw")]
+#[serde(untagged, try_from = "JsonRpcRawMessage")]
 pub enum JsonRpcMessage {
     Request(JsonRpcRequest),
     Response(JsonRpcResponse),
     Notification(JsonRpcNotification),
     Error(JsonRpcError),
+    /// A JSON-RPC batch, serialized as an array of individual messages
+    Batch(Vec<JsonRpcMessage>),
+    /// An element of a received batch that is not a valid message, with the Invalid
+    /// Request error to answer it with. The other elements of the batch are still handled.
+    Invalid(JsonRpcError),
     Nil, // used to respond to notifications
 }
 
+impl JsonRpcMessage {
+    /// Returns true if this is a request, or a batch containing at least one request,
+    /// i.e. the peer is expected to send something back
+    pub fn expects_response(&self) -> bool {
+        match self {
+            JsonRpcMessage::Request(_) => true,
+            JsonRpcMessage::Batch(messages) => messages.iter().any(Self::expects_response),
+            _ => false,
+        }
+    }
+}
+
+/// Wire shape of an incoming message, either a single object or a batch array
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonRpcRawMessage {
+    Batch(Vec<Value>),
+    Single(JsonRpcRaw),
+}
+
+impl TryFrom<JsonRpcRawMessage> for JsonRpcMessage {
+    type Error = String;
+
+    fn try_from(
+        raw: JsonRpcRawMessage,
+    ) -> Result<Self, <Self as TryFrom<JsonRpcRawMessage>>::Error> {
+        match raw {
+            JsonRpcRawMessage::Single(raw) => JsonRpcMessage::try_from(raw),
+            JsonRpcRawMessage::Batch(raws) => {
+                // An empty array is not a valid batch per the JSON-RPC spec
+                if raws.is_empty() {
+                    return Err("Invalid JSON-RPC batch: empty array".to_string());
+                }
+                // Each element is parsed on its own, so that one bad element doesn't fail
+                // the whole batch
+                Ok(JsonRpcMessage::Batch(
+                    raws.into_iter().map(batch_element).collect(),
+                ))
+            }
+        }
+    }
+}
+
+/// Parse one element of a batch, turning an invalid one into [`JsonRpcMessage::Invalid`]
+fn batch_element(value: Value) -> JsonRpcMessage {
+    // Answer with the element's id when it has a usable one, null otherwise
+    let id = value
+        .get("id")
+        .and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok());
+    let message = if value.get("jsonrpc").and_then(Value::as_str) == Some("2.0") {
+        serde_json::from_value::<JsonRpcRaw>(value)
+            .map_err(|e| e.to_string())
+            .and_then(JsonRpcMessage::try_from)
+    } else {
+        Err("Missing or invalid jsonrpc version".to_string())
+    };
+    message.unwrap_or_else(|message| {
+        JsonRpcMessage::Invalid(JsonRpcError {
+            jsonrpc: "2.0".to_string(),
+            id,
+            error: ErrorData {
+                code: INVALID_REQUEST,
+                message: format!("Invalid Request: {}", message),
+                data: None,
+            },
+        })
+    })
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct JsonRpcRaw {
     jsonrpc: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    id: Option<u64>,
+    // The outer Option tracks whether the id field was present at all, so that a request
+    // carrying `"id": null` is not mistaken for a notification
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_present"
+    )]
+    id: Option<Option<RequestId>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     method: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -71,16 +218,24 @@ struct JsonRpcRaw {
     error: Option<ErrorData>,
 }
 
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
 impl TryFrom<JsonRpcRaw> for JsonRpcMessage {
     type Error = String;
 
     fn try_from(raw: JsonRpcRaw) -> Result<Self, <Self as TryFrom<JsonRpcRaw>>::Error> {
         // If it has an error field, it's an error response
-        if raw.error.is_some() {
+        if let Some(error) = raw.error {
             return Ok(JsonRpcMessage::Error(JsonRpcError {
                 jsonrpc: raw.jsonrpc,
-                id: raw.id,
-                error: raw.error.unwrap(),
+                id: raw.id.flatten(),
+                error,
             }));
         }
 
@@ -88,7 +243,7 @@ impl TryFrom<JsonRpcRaw> for JsonRpcMessage {
         if raw.result.is_some() {
             return Ok(JsonRpcMessage::Response(JsonRpcResponse {
                 jsonrpc: raw.jsonrpc,
-                id: raw.id,
+                id: raw.id.flatten(),
                 result: raw.result,
                 error: None,
             }));
@@ -96,17 +251,17 @@ impl TryFrom<JsonRpcRaw> for JsonRpcMessage {
 
         // If we have a method, it's either a notification or request
         if let Some(method) = raw.method {
-            if raw.id.is_none() {
+            let Some(id) = raw.id else {
                 return Ok(JsonRpcMessage::Notification(JsonRpcNotification {
                     jsonrpc: raw.jsonrpc,
                     method,
                     params: raw.params,
                 }));
-            }
+            };
 
             return Ok(JsonRpcMessage::Request(JsonRpcRequest {
                 jsonrpc: raw.jsonrpc,
-                id: raw.id,
+                id,
                 method,
                 params: raw.params,
             }));
@@ -155,6 +310,8 @@ pub struct InitializeResult {
     pub server_info: Implementation,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub instructions: Option<String>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -171,6 +328,10 @@ pub struct ServerCapabilities {
     pub resources: Option<ResourcesCapability>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<ToolsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logging: Option<LoggingCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completions: Option<CompletionsCapability>,
     // Add other capabilities as needed
 }
 
@@ -193,17 +354,108 @@ pub struct ToolsCapability {
     pub list_changed: Option<bool>,
 }
 
+/// Present if the server can send log messages to the client
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct LoggingCapability {}
+
+/// Present if the server can suggest values for prompt and resource template arguments
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct CompletionsCapability {}
+
+/// The capabilities a client announces in its `initialize` request
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ClientCapabilities {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roots: Option<RootsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<SamplingCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elicitation: Option<ElicitationCapability>,
+    // Add other capabilities as needed
+}
+
+/// Present if the client can tell the server which roots it may operate on
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RootsCapability {
+    /// Whether the client notifies the server when its roots change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_changed: Option<bool>,
+}
+
+/// Present if the client can sample from an LLM on behalf of the server
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct SamplingCapability {}
+
+/// Present if the client can ask its user for input on behalf of the server
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ElicitationCapability {}
+
+/// The params of the requests listing tools, resources, resource templates or prompts
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct PaginatedRequest {
+    /// The `next_cursor` of the previous page, to get the page after it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ListResourcesResult {
     pub resources: Vec<Resource>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub next_cursor: Option<String>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ListResourceTemplatesResult {
+    pub resource_templates: Vec<ResourceTemplate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// The params of a `resources/subscribe` request, asking to be notified whenever the
+/// resource at `uri` changes
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SubscribeRequest {
+    pub uri: String,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// The params of a `resources/unsubscribe` request, ending a subscription
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UnsubscribeRequest {
+    pub uri: String,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// The params of a `notifications/resources/updated` notification, telling a subscribed
+/// client that the resource at `uri` changed and may be read again
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResourceUpdatedNotification {
+    pub uri: String,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl ResourceUpdatedNotification {
+    pub const METHOD: &'static str = "notifications/resources/updated";
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ReadResourceResult {
     pub contents: Vec<ResourceContents>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -212,19 +464,64 @@ pub struct ListToolsResult {
     pub tools: Vec<Tool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub next_cursor: Option<String>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CallToolResult {
     pub content: Vec<Content>,
+    /// The result as a JSON object matching the tool's output schema, if it declares one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl CallToolResult {
+    /// A successful result made of `content`
+    pub fn success(content: Vec<Content>) -> Self {
+        Self {
+            content,
+            structured_content: None,
+            is_error: None,
+            meta: None,
+        }
+    }
+
+    /// A successful result holding structured output. The output is repeated as JSON text
+    /// for clients that only read the content.
+    pub fn structured(value: Value) -> Self {
+        Self {
+            content: vec![Content::text(value.to_string())],
+            structured_content: Some(value),
+            is_error: None,
+            meta: None,
+        }
+    }
+
+    /// A failed result, with `content` explaining the failure to the model
+    pub fn error(content: Vec<Content>) -> Self {
+        Self {
+            content,
+            structured_content: None,
+            is_error: Some(true),
+            meta: None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct ListPromptsResult {
     pub prompts: Vec<Prompt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -232,10 +529,280 @@ pub struct GetPromptResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub messages: Vec<PromptMessage>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct EmptyResult {}
+impl GetPromptResult {
+    pub fn new(messages: Vec<PromptMessage>) -> Self {
+        Self {
+            description: None,
+            messages,
+            meta: None,
+        }
+    }
+
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+impl From<Vec<PromptMessage>> for GetPromptResult {
+    fn from(messages: Vec<PromptMessage>) -> Self {
+        Self::new(messages)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct EmptyResult {
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// A token identifying the request a progress notification is about. It is chosen by the
+/// sender of the request and, like a request id, may be a number or a string.
+pub type ProgressToken = RequestId;
+
+/// The params of a `notifications/progress` notification, reporting how far along a
+/// long-running request is
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressNotification {
+    /// The token from the `_meta` of the request this progress is about
+    pub progress_token: ProgressToken,
+    /// The progress so far, which increases with every notification
+    pub progress: f64,
+    /// The total amount of work, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<f64>,
+    /// A human readable description of the current progress
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl ProgressNotification {
+    pub const METHOD: &'static str = "notifications/progress";
+}
+
+/// The severity of a log message, following the syslog levels of RFC 5424. Levels are
+/// ordered from least to most severe.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum LoggingLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+/// The params of a `logging/setLevel` request, by which the client asks to receive log
+/// messages at `level` and above
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SetLevelRequest {
+    pub level: LoggingLevel,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// The params of a `notifications/message` notification, carrying a log message from the
+/// server to the client
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LoggingMessageNotification {
+    pub level: LoggingLevel,
+    /// The name of the logger that issued the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logger: Option<String>,
+    /// The message itself, any JSON value
+    pub data: Value,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl LoggingMessageNotification {
+    pub const METHOD: &'static str = "notifications/message";
+}
+
+/// A list offered by the server that changed, announced to the client with a
+/// `notifications/<list>/list_changed` notification so that it can fetch the list again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListChanged {
+    Tools,
+    Prompts,
+    Resources,
+}
+
+impl ListChanged {
+    /// The method of the notification announcing the change
+    pub fn method(self) -> &'static str {
+        match self {
+            ListChanged::Tools => "notifications/tools/list_changed",
+            ListChanged::Prompts => "notifications/prompts/list_changed",
+            ListChanged::Resources => "notifications/resources/list_changed",
+        }
+    }
+
+    /// The list a notification method announces a change of, if it is one of these
+    pub fn from_method(method: &str) -> Option<Self> {
+        [
+            ListChanged::Tools,
+            ListChanged::Prompts,
+            ListChanged::Resources,
+        ]
+        .into_iter()
+        .find(|list| list.method() == method)
+    }
+}
+
+/// A directory or file the client allows the server to operate on, such as a project
+/// folder. The server should stay within the roots the client gives it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Root {
+    /// The URI of the root, currently always a `file://` URI
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Root {
+    pub fn new<S: Into<String>>(uri: S) -> Self {
+        Self {
+            uri: uri.into(),
+            name: None,
+        }
+    }
+
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+}
+
+/// The params of a `roots/list` request, which the server sends to the client
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct ListRootsRequest {
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl ListRootsRequest {
+    pub const METHOD: &'static str = "roots/list";
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ListRootsResult {
+    pub roots: Vec<Root>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// The params of a `notifications/roots/list_changed` notification, telling the server
+/// that the client's roots changed and should be listed again
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct RootsListChangedNotification {
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl RootsListChangedNotification {
+    pub const METHOD: &'static str = "notifications/roots/list_changed";
+}
+
+/// What a completion request completes an argument of
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum Reference {
+    /// A prompt, by name
+    #[serde(rename = "ref/prompt")]
+    Prompt { name: String },
+    /// A resource template, by its URI template
+    #[serde(rename = "ref/resource")]
+    Resource { uri: String },
+}
+
+/// The argument being completed, with what the user typed so far
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ArgumentInfo {
+    pub name: String,
+    pub value: String,
+}
+
+/// The params of a `completion/complete` request
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CompleteRequest {
+    #[serde(rename = "ref")]
+    pub reference: Reference,
+    pub argument: ArgumentInfo,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl CompleteRequest {
+    pub const METHOD: &'static str = "completion/complete";
+}
+
+/// Suggested values for an argument, best matches first
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Completion {
+    /// At most [`Completion::MAX_VALUES`] values
+    pub values: Vec<String>,
+    /// The number of matches, which may exceed the values returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u32>,
+    /// Whether there are more matches than the values returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_more: Option<bool>,
+}
+
+impl Completion {
+    /// The most values a completion may hold
+    pub const MAX_VALUES: usize = 100;
+
+    /// Create a completion from all the matches, keeping the first
+    /// [`Completion::MAX_VALUES`] and recording how many there were
+    pub fn new(mut values: Vec<String>) -> Self {
+        let total = values.len();
+        values.truncate(Self::MAX_VALUES);
+        Self {
+            has_more: Some(total > values.len()),
+            total: Some(total as u32),
+            values,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CompleteResult {
+    pub completion: Completion,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// The params of a `notifications/cancelled` notification, telling the receiver that the
+/// sender is no longer interested in the result of a request it made. The receiver should
+/// stop working on the request and send no response for it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelledNotification {
+    /// The id of the request to cancel
+    pub request_id: RequestId,
+    /// Why the request was cancelled, which may be logged or shown to the user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl CancelledNotification {
+    pub const METHOD: &'static str = "notifications/cancelled";
+}
 
 #[cfg(test)]
 mod tests {
@@ -268,7 +835,7 @@ mod tests {
     fn test_request_conversion() {
         let raw = JsonRpcRaw {
             jsonrpc: "2.0".to_string(),
-            id: Some(1),
+            id: Some(Some(RequestId::Number(1.into()))),
             method: Some("request".to_string()),
             params: Some(json!({"key": "value"})),
             result: None,
@@ -279,11 +846,207 @@ mod tests {
         match message {
             JsonRpcMessage::Request(r) => {
                 assert_eq!(r.jsonrpc, "2.0");
-                assert_eq!(r.id, Some(1));
+                assert_eq!(r.id, Some(RequestId::Number(1.into())));
                 assert_eq!(r.method, "request");
                 assert_eq!(r.params.unwrap(), json!({"key": "value"}));
             }
             _ => panic!("Expected Request"),
         }
     }
+
+    #[test]
+    fn test_request_id_round_trip() {
+        for (id, expected) in [
+            (RequestId::Number(42.into()), json!(42)),
+            (RequestId::from(-1i64), json!(-1)),
+            (
+                RequestId::Number(serde_json::Number::from_f64(1.5).unwrap()),
+                json!(1.5),
+            ),
+            (RequestId::String("abc-123".to_string()), json!("abc-123")),
+        ] {
+            let request = JsonRpcMessage::Request(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(id.clone()),
+                method: "ping".to_string(),
+                params: None,
+            });
+            let value = serde_json::to_value(&request).unwrap();
+            assert_eq!(value["id"], expected);
+            assert_eq!(
+                serde_json::from_value::<JsonRpcMessage>(value).unwrap(),
+                request
+            );
+
+            let response = JsonRpcMessage::Response(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: Some(id),
+                result: Some(json!({})),
+                error: None,
+            });
+            let value = serde_json::to_value(&response).unwrap();
+            assert_eq!(value["id"], expected);
+            assert_eq!(
+                serde_json::from_value::<JsonRpcMessage>(value).unwrap(),
+                response
+            );
+        }
+    }
+
+    #[test]
+    fn test_null_request_id() {
+        let message: JsonRpcMessage =
+            serde_json::from_value(json!({"jsonrpc": "2.0", "id": null, "method": "ping"}))
+                .unwrap();
+        match &message {
+            JsonRpcMessage::Request(r) => assert_eq!(r.id, None),
+            _ => panic!("Expected Request"),
+        }
+        let value = serde_json::to_value(&message).unwrap();
+        assert_eq!(
+            value,
+            json!({"jsonrpc": "2.0", "id": null, "method": "ping"})
+        );
+        assert_eq!(
+            serde_json::from_value::<JsonRpcMessage>(value).unwrap(),
+            message
+        );
+
+        let error = JsonRpcMessage::Error(JsonRpcError {
+            jsonrpc: "2.0".to_string(),
+            id: None,
+            error: ErrorData {
+                code: PARSE_ERROR,
+                message: "Parse error".to_string(),
+                data: None,
+            },
+        });
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["id"], Value::Null);
+        assert_eq!(
+            serde_json::from_value::<JsonRpcMessage>(value).unwrap(),
+            error
+        );
+    }
+
+    #[test]
+    fn test_batch_round_trip() {
+        let value = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "tools/list"},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "id": "two", "method": "prompts/list", "params": {}}
+        ]);
+
+        let message: JsonRpcMessage = serde_json::from_value(value.clone()).unwrap();
+        match &message {
+            JsonRpcMessage::Batch(messages) => {
+                assert_eq!(messages.len(), 3);
+                assert!(matches!(messages[0], JsonRpcMessage::Request(_)));
+                assert!(matches!(messages[1], JsonRpcMessage::Notification(_)));
+                assert!(matches!(messages[2], JsonRpcMessage::Request(_)));
+            }
+            _ => panic!("Expected Batch"),
+        }
+        assert!(message.expects_response());
+        assert_eq!(serde_json::to_value(&message).unwrap(), value);
+    }
+
+    #[test]
+    fn test_invalid_batch_elements() {
+        let message: JsonRpcMessage = serde_json::from_value(json!([
+            1,
+            {"jsonrpc": "2.0", "id": 1, "method": "ping"},
+            {"jsonrpc": "1.0", "id": 2, "method": "ping"},
+            {"jsonrpc": "2.0", "id": 3, "method": 4}
+        ]))
+        .unwrap();
+        let JsonRpcMessage::Batch(messages) = message else {
+            panic!("Expected Batch");
+        };
+        assert!(matches!(messages[1], JsonRpcMessage::Request(_)));
+        let invalid: Vec<_> = messages
+            .iter()
+            .filter_map(|message| match message {
+                JsonRpcMessage::Invalid(error) => Some(error),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(invalid.len(), 3);
+        assert!(invalid.iter().all(|e| e.error.code == INVALID_REQUEST));
+        let ids: Vec<_> = invalid.iter().map(|e| e.id.clone()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                None,
+                Some(RequestId::Number(2.into())),
+                Some(RequestId::Number(3.into()))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_batch_is_rejected() {
+        assert!(serde_json::from_value::<JsonRpcMessage>(json!([])).is_err());
+    }
+
+    #[test]
+    fn test_progress_token() {
+        let request: JsonRpcRequest = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "index", "_meta": {"progressToken": "index-1"}}
+        }))
+        .unwrap();
+        assert_eq!(
+            request.progress_token(),
+            Some(ProgressToken::from("index-1"))
+        );
+
+        let notification = ProgressNotification {
+            progress_token: ProgressToken::Number(7.into()),
+            progress: 50.0,
+            total: Some(100.0),
+            message: None,
+            meta: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&notification).unwrap(),
+            json!({"progressToken": 7, "progress": 50.0, "total": 100.0})
+        );
+    }
+
+    #[test]
+    fn test_logging_level_order() {
+        assert!(LoggingLevel::Debug < LoggingLevel::Info);
+        assert!(LoggingLevel::Warning < LoggingLevel::Error);
+        assert_eq!(
+            serde_json::to_value(LoggingLevel::Warning).unwrap(),
+            json!("warning")
+        );
+        let request: SetLevelRequest =
+            serde_json::from_value(json!({"level": "critical"})).unwrap();
+        assert_eq!(request.level, LoggingLevel::Critical);
+    }
+
+    #[test]
+    fn test_complete_request_serialization() {
+        let request: CompleteRequest = serde_json::from_value(json!({
+            "ref": {"type": "ref/prompt", "name": "code_review"},
+            "argument": {"name": "language", "value": "py"}
+        }))
+        .unwrap();
+        assert_eq!(
+            request.reference,
+            Reference::Prompt {
+                name: "code_review".to_string()
+            }
+        );
+
+        let values = (0..150).map(|i| i.to_string()).collect();
+        let completion = Completion::new(values);
+        assert_eq!(completion.values.len(), Completion::MAX_VALUES);
+        assert_eq!(completion.total, Some(150));
+        assert_eq!(completion.has_more, Some(true));
+    }
 }
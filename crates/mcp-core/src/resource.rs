@@ -1,10 +1,12 @@
 /// Resources that servers provide to clients
 use anyhow::{anyhow, Result};
+use base64::engine::{general_purpose::STANDARD as BASE64_STANDARD, Engine};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use url::Url;
 
-use crate::content::Annotations;
+use crate::{content::Annotations, uri_template::UriTemplate};
 
 const EPSILON: f32 = 1e-6; // Tolerance for floating point comparison
 
@@ -16,16 +18,73 @@ pub struct Resource {
     pub uri: String,
     /// Name of the resource
     pub name: String,
+    /// Human readable name of the resource, shown instead of `name` if set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
     /// Optional description of the resource
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    /// MIME type of the resource content ("text" or "blob")
+    /// MIME type of the resource content (e.g. "text/markdown" or "image/png")
     #[serde(default = "default_mime_type")]
     pub mime_type: String,
+    /// Size of the resource content in bytes, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+}
+
+/// A template describing a family of resources, whose URIs follow an RFC 6570 URI template
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTemplate {
+    /// URI template the resources match (e.g., "file:///logs/{date}")
+    pub uri_template: String,
+    /// Name of the kind of resource this template provides
+    pub name: String,
+    /// Optional description of the resources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// MIME type of all resources matching the template, if they share one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub annotations: Option<Annotations>,
 }
 
+impl ResourceTemplate {
+    /// Creates a new ResourceTemplate, checking that the URI template is valid
+    pub fn new<S: Into<String>>(uri_template: S, name: S) -> Result<Self> {
+        let uri_template = uri_template.into();
+        UriTemplate::parse(&uri_template)?;
+
+        Ok(Self {
+            uri_template,
+            name: name.into(),
+            description: None,
+            mime_type: None,
+            annotations: None,
+        })
+    }
+
+    /// Sets the description of the resource template
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Sets the MIME type of the resources matching the template
+    pub fn with_mime_type<S: Into<String>>(mut self, mime_type: S) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    /// Match a resource URI against the template, returning the values of its variables
+    pub fn match_uri(&self, uri: &str) -> Option<HashMap<String, String>> {
+        UriTemplate::parse(&self.uri_template).ok()?.match_uri(uri)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase", untagged)]
 pub enum ResourceContents {
@@ -43,8 +102,84 @@ pub enum ResourceContents {
     },
 }
 
+impl ResourceContents {
+    /// Text contents, whose MIME type is filled in by the server from the resource if
+    /// not set with [`ResourceContents::with_mime_type`]
+    pub fn text<U: Into<String>, T: Into<String>>(uri: U, text: T) -> Self {
+        ResourceContents::TextResourceContents {
+            uri: uri.into(),
+            mime_type: None,
+            text: text.into(),
+        }
+    }
+
+    /// Binary contents, sent base64-encoded
+    pub fn blob<U: Into<String>>(uri: U, data: &[u8]) -> Self {
+        ResourceContents::BlobResourceContents {
+            uri: uri.into(),
+            mime_type: None,
+            blob: BASE64_STANDARD.encode(data),
+        }
+    }
+
+    pub fn with_mime_type<S: Into<String>>(mut self, mime_type: S) -> Self {
+        match &mut self {
+            ResourceContents::TextResourceContents { mime_type: m, .. }
+            | ResourceContents::BlobResourceContents { mime_type: m, .. } => {
+                *m = Some(mime_type.into())
+            }
+        }
+        self
+    }
+
+    pub fn uri(&self) -> &str {
+        match self {
+            ResourceContents::TextResourceContents { uri, .. }
+            | ResourceContents::BlobResourceContents { uri, .. } => uri,
+        }
+    }
+
+    pub fn mime_type(&self) -> Option<&str> {
+        match self {
+            ResourceContents::TextResourceContents { mime_type, .. }
+            | ResourceContents::BlobResourceContents { mime_type, .. } => mime_type.as_deref(),
+        }
+    }
+}
+
+/// The MIME type of resources that don't specify one
+pub const DEFAULT_MIME_TYPE: &str = "text/plain";
+
 fn default_mime_type() -> String {
-    "text".to_string()
+    DEFAULT_MIME_TYPE.to_string()
+}
+
+/// Check that `mime_type` looks like "type/subtype", optionally with parameters, mapping
+/// the "text" and "blob" shorthands older versions accepted to their MIME types
+fn normalize_mime_type(mime_type: Option<String>) -> String {
+    let Some(mime_type) = mime_type else {
+        return default_mime_type();
+    };
+    match mime_type.as_str() {
+        "text" => return default_mime_type(),
+        "blob" => return "application/octet-stream".to_string(),
+        _ => {}
+    }
+
+    let essence = mime_type.split(';').next().unwrap_or_default().trim();
+    let valid = essence.split_once('/').is_some_and(|(kind, subtype)| {
+        let token = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+        };
+        token(kind) && token(subtype)
+    });
+    if valid {
+        mime_type
+    } else {
+        default_mime_type()
+    }
 }
 
 impl Resource {
@@ -63,22 +198,18 @@ impl Resource {
             Some(n) => n,
             None => url
                 .path_segments()
-                .and_then(|segments| segments.last())
+                .and_then(|mut segments| segments.next_back())
                 .unwrap_or("unnamed")
                 .to_string(),
         };
 
-        // Use provided mime_type or default
-        let mime_type = match mime_type {
-            Some(t) if t == "text" || t == "blob" => t,
-            _ => default_mime_type(),
-        };
-
         Ok(Self {
             uri: uri.to_string(),
             name,
+            title: None,
             description: None,
-            mime_type,
+            mime_type: normalize_mime_type(mime_type),
+            size: None,
             annotations: Some(Annotations::for_resource(0.0, Utc::now())),
         })
     }
@@ -93,17 +224,13 @@ impl Resource {
         let uri_string = uri.into();
         Url::parse(&uri_string).map_err(|e| anyhow!("Invalid URI: {}", e))?;
 
-        // Use provided mime_type or default
-        let mime_type = match mime_type {
-            Some(t) if t == "text" || t == "blob" => t,
-            _ => default_mime_type(),
-        };
-
         Ok(Self {
             uri: uri_string,
             name: name.into(),
+            title: None,
             description: None,
-            mime_type,
+            mime_type: normalize_mime_type(mime_type),
+            size: None,
             annotations: Some(Annotations::for_resource(priority, Utc::now())),
         })
     }
@@ -155,13 +282,21 @@ impl Resource {
         self
     }
 
-    /// Sets the MIME type of the resource
+    /// Sets the MIME type of the resource, "text/plain" if it isn't a valid MIME type
     pub fn with_mime_type<S: Into<String>>(mut self, mime_type: S) -> Self {
-        let mime_type = mime_type.into();
-        match mime_type.as_str() {
-            "text" | "blob" => self.mime_type = mime_type,
-            _ => self.mime_type = default_mime_type(),
-        }
+        self.mime_type = normalize_mime_type(Some(mime_type.into()));
+        self
+    }
+
+    /// Sets the human readable title of the resource
+    pub fn with_title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Sets the size of the resource content in bytes
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
         self
     }
 }
@@ -184,7 +319,7 @@ mod tests {
         let resource = Resource::new(&uri, Some("text".to_string()), None)?;
         assert!(resource.uri.starts_with("file:///"));
         assert_eq!(resource.priority(), Some(0.0));
-        assert_eq!(resource.mime_type, "text");
+        assert_eq!(resource.mime_type, "text/plain");
         assert_eq!(resource.scheme()?, "file");
 
         Ok(())
@@ -204,7 +339,7 @@ mod tests {
         assert_eq!(resource.uri, uri);
         assert_eq!(resource.name, "test.txt");
         assert_eq!(resource.priority(), Some(0.5));
-        assert_eq!(resource.mime_type, "text");
+        assert_eq!(resource.mime_type, "text/plain");
         assert_eq!(resource.scheme()?, "str");
 
         Ok(())
@@ -213,19 +348,27 @@ mod tests {
     #[test]
     fn test_mime_type_validation() -> Result<()> {
         // Test valid mime types
+        let resource = Resource::new("file:///test.md", Some("text/markdown".to_string()), None)?;
+        assert_eq!(resource.mime_type, "text/markdown");
+
+        let mime_type = "text/csv; charset=utf-8".to_string();
+        let resource = Resource::new("file:///test.csv", Some(mime_type.clone()), None)?;
+        assert_eq!(resource.mime_type, mime_type);
+
+        // Test the legacy shorthands map to MIME types
         let resource = Resource::new("file:///test.txt", Some("text".to_string()), None)?;
-        assert_eq!(resource.mime_type, "text");
+        assert_eq!(resource.mime_type, "text/plain");
 
         let resource = Resource::new("file:///test.bin", Some("blob".to_string()), None)?;
-        assert_eq!(resource.mime_type, "blob");
+        assert_eq!(resource.mime_type, "application/octet-stream");
 
-        // Test invalid mime type defaults to "text"
+        // Test invalid mime type defaults to "text/plain"
         let resource = Resource::new("file:///test.txt", Some("invalid".to_string()), None)?;
-        assert_eq!(resource.mime_type, "text");
+        assert_eq!(resource.mime_type, "text/plain");
 
-        // Test None defaults to "text"
+        // Test None defaults to "text/plain"
         let resource = Resource::new("file:///test.txt", None, None)?;
-        assert_eq!(resource.mime_type, "text");
+        assert_eq!(resource.mime_type, "text/plain");
 
         Ok(())
     }
@@ -241,14 +384,21 @@ mod tests {
 
     #[test]
     fn test_with_mime_type() -> Result<()> {
-        let resource =
-            Resource::with_uri("file:///test.txt", "test.txt", 0.0, None)?.with_mime_type("blob");
-
-        assert_eq!(resource.mime_type, "blob");
-
-        // Test invalid mime type defaults to "text"
+        let resource = Resource::with_uri("file:///test.png", "test.png", 0.0, None)?
+            .with_mime_type("image/png")
+            .with_title("Test image")
+            .with_size(1024);
+
+        assert_eq!(resource.mime_type, "image/png");
+        assert_eq!(
+            serde_json::to_value(&resource)?["title"],
+            serde_json::json!("Test image")
+        );
+        assert_eq!(serde_json::to_value(&resource)?["size"], 1024);
+
+        // Test invalid mime type defaults to "text/plain"
         let resource = resource.with_mime_type("invalid");
-        assert_eq!(resource.mime_type, "text");
+        assert_eq!(resource.mime_type, "text/plain");
         Ok(())
     }
 
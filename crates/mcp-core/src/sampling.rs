@@ -0,0 +1,174 @@
+/// Sampling lets servers ask the client's LLM for a completion, through `sampling/createMessage`
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{content::Content, meta::Meta, role::Role};
+
+/// A message in the conversation sent to the LLM
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplingMessage {
+    pub role: Role,
+    pub content: Content,
+}
+
+impl SamplingMessage {
+    /// Create a text message from the user
+    pub fn user_text<S: Into<String>>(text: S) -> Self {
+        Self {
+            role: Role::User,
+            content: Content::text(text),
+        }
+    }
+
+    /// Create a text message from the assistant
+    pub fn assistant_text<S: Into<String>>(text: S) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: Content::text(text),
+        }
+    }
+}
+
+/// A hint about which model to use, matched loosely by the client (e.g. "claude-3" or
+/// "sonnet")
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct ModelHint {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// The server's preferences for the model the client picks. The client makes the final
+/// choice and may ignore them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPreferences {
+    /// Model names to consider, in order of preference
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hints: Option<Vec<ModelHint>>,
+    /// How much to prioritize a low cost, from 0 to 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_priority: Option<f32>,
+    /// How much to prioritize a fast response, from 0 to 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed_priority: Option<f32>,
+    /// How much to prioritize a capable model, from 0 to 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intelligence_priority: Option<f32>,
+}
+
+/// Which MCP servers' context the client should include in the prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IncludeContext {
+    None,
+    ThisServer,
+    AllServers,
+}
+
+/// The params of a `sampling/createMessage` request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMessageRequest {
+    pub messages: Vec<SamplingMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_preferences: Option<ModelPreferences>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_context: Option<IncludeContext>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    pub max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    /// Provider specific metadata, passed through to the LLM provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl CreateMessageRequest {
+    pub const METHOD: &'static str = "sampling/createMessage";
+
+    /// Create a request for at most `max_tokens` tokens in reply to `messages`
+    pub fn new(messages: Vec<SamplingMessage>, max_tokens: u32) -> Self {
+        Self {
+            messages,
+            model_preferences: None,
+            system_prompt: None,
+            include_context: None,
+            temperature: None,
+            max_tokens,
+            stop_sequences: None,
+            metadata: None,
+            meta: None,
+        }
+    }
+
+    pub fn with_system_prompt<S: Into<String>>(mut self, system_prompt: S) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+
+    pub fn with_model_preferences(mut self, model_preferences: ModelPreferences) -> Self {
+        self.model_preferences = Some(model_preferences);
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+}
+
+/// The result of a `sampling/createMessage` request, the message the LLM generated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMessageResult {
+    pub role: Role,
+    pub content: Content,
+    /// The name of the model that generated the message
+    pub model: String,
+    /// Why generation stopped, e.g. "endTurn", "stopSequence" or "maxTokens"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_create_message_request_serialization() {
+        let request = CreateMessageRequest::new(vec![SamplingMessage::user_text("Hi")], 100)
+            .with_model_preferences(ModelPreferences {
+                hints: Some(vec![ModelHint {
+                    name: Some("claude".to_string()),
+                }]),
+                intelligence_priority: Some(0.5),
+                ..Default::default()
+            });
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "messages": [{"role": "user", "content": {"type": "text", "text": "Hi"}}],
+                "modelPreferences": {"hints": [{"name": "claude"}], "intelligencePriority": 0.5},
+                "maxTokens": 100
+            })
+        );
+
+        let result: CreateMessageResult = serde_json::from_value(json!({
+            "role": "assistant",
+            "content": {"type": "text", "text": "Hello"},
+            "model": "claude-3-sonnet",
+            "stopReason": "endTurn"
+        }))
+        .unwrap();
+        assert_eq!(result.stop_reason.as_deref(), Some("endTurn"));
+    }
+}
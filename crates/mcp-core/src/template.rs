@@ -0,0 +1,475 @@
+//! A small templating language for prompts, see [`Template`]
+
+use std::collections::BTreeSet;
+
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Errors from parsing or rendering a [`Template`]
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TemplateError {
+    #[error("Syntax error at byte {position}: {message}")]
+    Syntax { position: usize, message: String },
+    #[error("Placeholder '{0}' is not a declared argument")]
+    UndeclaredArgument(String),
+    #[error("Missing required argument: '{0}'")]
+    MissingArgument(String),
+    #[error("Unknown argument: '{0}'")]
+    UnknownArgument(String),
+    #[error("Invalid argument '{name}': {message}")]
+    InvalidArgument { name: String, message: String },
+    #[error("Cannot loop over '{0}', which is not an array")]
+    NotIterable(String),
+}
+
+impl TemplateError {
+    /// Whether the error comes from the arguments given to render the template, rather
+    /// than from the template itself
+    pub fn is_argument_error(&self) -> bool {
+        !matches!(
+            self,
+            TemplateError::Syntax { .. } | TemplateError::UndeclaredArgument(_)
+        )
+    }
+
+    fn syntax<S: Into<String>>(position: usize, message: S) -> Self {
+        TemplateError::Syntax {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+/// A parsed prompt template.
+///
+/// Templates are text with tags in single braces:
+/// - `{name}` inserts the value of `name`, and `{name.field}` a field of an object value
+/// - `{#if name}...{else}...{/if}` renders the first part if `name` is set to a value
+///   other than false, 0, an empty string or an empty array or object, the optional
+///   `{else}` part otherwise
+/// - `{#each name as item}...{/each}` renders its body once for every element of the
+///   array `name`, with `{item}` set to the element
+///
+/// `{{` and `}}` stand for literal braces, any other brace is an error. Values are
+/// inserted as they are and never parsed as template text, so arguments can't inject
+/// tags.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Value(Path),
+    If {
+        condition: Path,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        items: Path,
+        binding: String,
+        body: Vec<Node>,
+    },
+}
+
+/// A dotted path such as `user.name`, made of a variable and fields
+#[derive(Debug, Clone, PartialEq)]
+struct Path {
+    variable: String,
+    fields: Vec<String>,
+}
+
+impl Path {
+    fn parse(text: &str, position: usize) -> Result<Self, TemplateError> {
+        let mut segments = text.split('.');
+        let variable = segments.next().unwrap_or_default();
+        let fields: Vec<&str> = segments.collect();
+        if !is_identifier(variable) || !fields.iter().all(|field| is_identifier(field)) {
+            return Err(TemplateError::syntax(
+                position,
+                format!(
+                    "'{}' is not a valid tag, write '{{{{' for a literal brace",
+                    text
+                ),
+            ));
+        }
+        Ok(Self {
+            variable: variable.to_string(),
+            fields: fields.into_iter().map(str::to_string).collect(),
+        })
+    }
+
+    fn display(&self) -> String {
+        std::iter::once(self.variable.as_str())
+            .chain(self.fields.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// A block opened by `{#if}` or `{#each}` and not closed yet
+enum Block {
+    If {
+        condition: Path,
+        /// The nodes before `{else}`, once it was seen
+        then: Option<Vec<Node>>,
+    },
+    Each {
+        items: Path,
+        binding: String,
+    },
+}
+
+impl Template {
+    /// Parse a template, failing on malformed tags and unbalanced blocks
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        // The nodes of the root, then of every open block with its opening position
+        let mut nodes = Vec::new();
+        let mut open: Vec<(Block, usize, Vec<Node>)> = Vec::new();
+        let mut text = String::new();
+        let mut rest = source;
+        let mut position = 0;
+        while let Some(index) = rest.find(['{', '}']) {
+            text.push_str(&rest[..index]);
+            let brace = rest.as_bytes()[index];
+            let tag_position = position + index;
+
+            // A doubled brace is a literal one
+            if rest.as_bytes().get(index + 1) == Some(&brace) {
+                text.push(brace as char);
+                rest = &rest[index + 2..];
+                position = tag_position + 2;
+                continue;
+            }
+            if brace == b'}' {
+                return Err(TemplateError::syntax(
+                    tag_position,
+                    "unmatched '}', write '}}' for a literal brace",
+                ));
+            }
+
+            let end = rest[index + 1..]
+                .find(['{', '}'])
+                .filter(|end| rest.as_bytes()[index + 1 + end] == b'}')
+                .ok_or_else(|| {
+                    TemplateError::syntax(
+                        tag_position,
+                        "unclosed tag, write '{{' for a literal brace",
+                    )
+                })?;
+            let tag = rest[index + 1..index + 1 + end].trim();
+            rest = &rest[index + end + 2..];
+            position = tag_position + end + 2;
+
+            let target = match open.last_mut() {
+                Some((_, _, block_nodes)) => block_nodes,
+                None => &mut nodes,
+            };
+            if !text.is_empty() {
+                target.push(Node::Text(std::mem::take(&mut text)));
+            }
+
+            if let Some(condition) = tag.strip_prefix("#if ") {
+                let condition = Path::parse(condition.trim(), tag_position)?;
+                open.push((
+                    Block::If {
+                        condition,
+                        then: None,
+                    },
+                    tag_position,
+                    Vec::new(),
+                ));
+            } else if let Some(each) = tag.strip_prefix("#each ") {
+                let (items, binding) = each.split_once(" as ").ok_or_else(|| {
+                    TemplateError::syntax(tag_position, "expected '{#each items as item}'")
+                })?;
+                let items = Path::parse(items.trim(), tag_position)?;
+                let binding = binding.trim();
+                if !is_identifier(binding) {
+                    return Err(TemplateError::syntax(
+                        tag_position,
+                        format!("'{}' is not a valid loop variable", binding),
+                    ));
+                }
+                open.push((
+                    Block::Each {
+                        items,
+                        binding: binding.to_string(),
+                    },
+                    tag_position,
+                    Vec::new(),
+                ));
+            } else if tag == "else" {
+                match open.last_mut() {
+                    Some((
+                        Block::If {
+                            then: then @ None, ..
+                        },
+                        _,
+                        block_nodes,
+                    )) => {
+                        *then = Some(std::mem::take(block_nodes));
+                    }
+                    _ => {
+                        return Err(TemplateError::syntax(
+                            tag_position,
+                            "'{else}' outside of '{#if}'",
+                        ))
+                    }
+                }
+            } else if tag == "/if" || tag == "/each" {
+                let node = match open.pop() {
+                    Some((Block::If { condition, then }, _, block_nodes)) if tag == "/if" => {
+                        match then {
+                            Some(then) => Node::If {
+                                condition,
+                                then,
+                                otherwise: block_nodes,
+                            },
+                            None => Node::If {
+                                condition,
+                                then: block_nodes,
+                                otherwise: Vec::new(),
+                            },
+                        }
+                    }
+                    Some((Block::Each { items, binding }, _, body)) if tag == "/each" => {
+                        Node::Each {
+                            items,
+                            binding,
+                            body,
+                        }
+                    }
+                    _ => {
+                        return Err(TemplateError::syntax(
+                            tag_position,
+                            format!("'{{{}}}' doesn't close an open block", tag),
+                        ))
+                    }
+                };
+                match open.last_mut() {
+                    Some((_, _, block_nodes)) => block_nodes.push(node),
+                    None => nodes.push(node),
+                }
+            } else {
+                target.push(Node::Value(Path::parse(tag, tag_position)?));
+            }
+        }
+        text.push_str(rest);
+
+        if let Some((_, position, _)) = open.last() {
+            return Err(TemplateError::syntax(*position, "block is never closed"));
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        Ok(Self { nodes })
+    }
+
+    /// The variables the template refers to, leaving out the loop variables
+    pub fn variables(&self) -> BTreeSet<String> {
+        fn collect(nodes: &[Node], bound: &mut Vec<String>, variables: &mut BTreeSet<String>) {
+            fn add(path: &Path, bound: &[String], variables: &mut BTreeSet<String>) {
+                if !bound.contains(&path.variable) {
+                    variables.insert(path.variable.clone());
+                }
+            }
+            for node in nodes {
+                match node {
+                    Node::Text(_) => {}
+                    Node::Value(path) => add(path, bound, variables),
+                    Node::If {
+                        condition,
+                        then,
+                        otherwise,
+                    } => {
+                        add(condition, bound, variables);
+                        collect(then, bound, variables);
+                        collect(otherwise, bound, variables);
+                    }
+                    Node::Each {
+                        items,
+                        binding,
+                        body,
+                    } => {
+                        add(items, bound, variables);
+                        bound.push(binding.clone());
+                        collect(body, bound, variables);
+                        bound.pop();
+                    }
+                }
+            }
+        }
+
+        let mut variables = BTreeSet::new();
+        collect(&self.nodes, &mut Vec::new(), &mut variables);
+        variables
+    }
+
+    /// Render the template with `values`. Variables without a value render as nothing.
+    pub fn render(&self, values: &Map<String, Value>) -> Result<String, TemplateError> {
+        let mut output = String::new();
+        render_nodes(&self.nodes, values, &mut Vec::new(), &mut output)?;
+        Ok(output)
+    }
+}
+
+fn render_nodes<'a>(
+    nodes: &'a [Node],
+    values: &'a Map<String, Value>,
+    bound: &mut Vec<(&'a str, &'a Value)>,
+    output: &mut String,
+) -> Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Value(path) => match lookup(path, values, bound) {
+                Value::Null => {}
+                Value::String(text) => output.push_str(text),
+                value => output.push_str(&value.to_string()),
+            },
+            Node::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let branch = if is_truthy(lookup(condition, values, bound)) {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, values, bound, output)?;
+            }
+            Node::Each {
+                items,
+                binding,
+                body,
+            } => match lookup(items, values, bound) {
+                Value::Null => {}
+                Value::Array(elements) => {
+                    for element in elements {
+                        bound.push((binding, element));
+                        let rendered = render_nodes(body, values, bound, output);
+                        bound.pop();
+                        rendered?;
+                    }
+                }
+                _ => return Err(TemplateError::NotIterable(items.display())),
+            },
+        }
+    }
+    Ok(())
+}
+
+fn lookup<'a>(
+    path: &Path,
+    values: &'a Map<String, Value>,
+    bound: &[(&'a str, &'a Value)],
+) -> &'a Value {
+    let root = bound
+        .iter()
+        .rev()
+        .find(|(name, _)| *name == path.variable)
+        .map(|(_, value)| *value)
+        .or_else(|| values.get(&path.variable));
+    path.fields
+        .iter()
+        .try_fold(root, |value, field| Some(value?.get(field)))
+        .flatten()
+        .unwrap_or(&Value::Null)
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64() != Some(0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(elements) => !elements.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+/// Escape `text` so that it renders as is when used as template text
+pub fn escape(text: &str) -> String {
+    text.replace('{', "{{").replace('}', "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(source: &str, values: Value) -> Result<String, TemplateError> {
+        Template::parse(source)?.render(values.as_object().unwrap())
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("Hello {name}!", json!({"name": "Ada"})).unwrap(),
+            "Hello Ada!"
+        );
+        assert_eq!(
+            render(
+                "{#if urgent}URGENT {else}FYI {/if}{#each items as item}[{item.id}]{/each}",
+                json!({"urgent": false, "items": [{"id": 1}, {"id": 2}]})
+            )
+            .unwrap(),
+            "FYI [1][2]"
+        );
+
+        // Braces are escaped by doubling them, and values are never parsed
+        assert_eq!(
+            render("{{\"code\": {code}}}", json!({"code": "{evil}"})).unwrap(),
+            "{\"code\": {evil}}"
+        );
+        assert_eq!(
+            render(&escape("{literal}"), json!({})).unwrap(),
+            "{literal}"
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        for source in [
+            "{unclosed",
+            "stray }",
+            "{#if a}no end",
+            "{/each}",
+            "{#if a}{/each}",
+            "{not a tag}",
+            "{else}",
+        ] {
+            assert!(
+                matches!(Template::parse(source), Err(TemplateError::Syntax { .. })),
+                "{}",
+                source
+            );
+        }
+        assert_eq!(
+            render("{#each count as n}{/each}", json!({"count": 3})),
+            Err(TemplateError::NotIterable("count".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        let template =
+            Template::parse("{a}{#each b as item}{item.c}{d}{/each}{#if e}{/if}").unwrap();
+        assert_eq!(
+            template.variables().into_iter().collect::<Vec<_>>(),
+            vec!["a", "b", "d", "e"]
+        );
+    }
+}
@@ -9,10 +9,19 @@ use serde_json::Value;
 pub struct Tool {
     /// The name of the tool
     pub name: String,
+    /// A human readable name for the tool, shown instead of `name` if present
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
     /// A description of what the tool does
     pub description: String,
     /// A JSON Schema object defining the expected parameters for the tool
     pub input_schema: Value,
+    /// A JSON Schema object describing the structured content the tool returns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
+    /// Hints about how the tool behaves
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
 }
 
 impl Tool {
@@ -24,10 +33,102 @@ impl Tool {
     {
         Tool {
             name: name.into(),
+            title: None,
             description: description.into(),
             input_schema,
+            output_schema: None,
+            annotations: None,
         }
     }
+
+    /// Declare that the tool returns structured content matching `output_schema`, which
+    /// must describe an object
+    pub fn with_output_schema(mut self, output_schema: Value) -> Self {
+        self.output_schema = Some(output_schema);
+        self
+    }
+
+    pub fn with_title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn with_annotations(mut self, annotations: ToolAnnotations) -> Self {
+        self.annotations = Some(annotations);
+        self
+    }
+
+    /// Returns true if the tool claims not to modify its environment
+    pub fn is_read_only(&self) -> bool {
+        self.annotations
+            .as_ref()
+            .and_then(|a| a.read_only_hint)
+            .unwrap_or(false)
+    }
+
+    /// Returns true if the tool may destroy or overwrite data, which is assumed unless it
+    /// is read-only or says otherwise
+    pub fn is_destructive(&self) -> bool {
+        !self.is_read_only()
+            && self
+                .annotations
+                .as_ref()
+                .and_then(|a| a.destructive_hint)
+                .unwrap_or(true)
+    }
+}
+
+/// Hints about the behavior of a tool, e.g. to let clients approve read-only tools without
+/// asking the user.
+///
+/// The hints are not guaranteed to be accurate, clients should only trust them when they
+/// trust the server. Unset hints take the most cautious value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolAnnotations {
+    /// A human readable name for the tool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The tool does not modify its environment. Defaults to false.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+    /// The tool may perform destructive updates, rather than only add to its environment.
+    /// Only meaningful when not read-only. Defaults to true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destructive_hint: Option<bool>,
+    /// Calling the tool again with the same arguments has no further effect. Only
+    /// meaningful when not read-only. Defaults to false.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idempotent_hint: Option<bool>,
+    /// The tool interacts with an open world of external entities, such as the web,
+    /// rather than a closed domain like a local database. Defaults to true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_world_hint: Option<bool>,
+}
+
+impl ToolAnnotations {
+    /// Annotations for a tool that only reads
+    pub fn read_only() -> Self {
+        Self {
+            read_only_hint: Some(true),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_destructive(mut self, destructive: bool) -> Self {
+        self.destructive_hint = Some(destructive);
+        self
+    }
+
+    pub fn with_idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent_hint = Some(idempotent);
+        self
+    }
+
+    pub fn with_open_world(mut self, open_world: bool) -> Self {
+        self.open_world_hint = Some(open_world);
+        self
+    }
 }
 
 /// A tool call request that an extension can execute
@@ -49,3 +150,37 @@ impl ToolCall {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_annotation_defaults() {
+        let schema = json!({"type": "object"});
+        let unannotated = Tool::new("rm", "Remove a file", schema.clone());
+        assert!(!unannotated.is_read_only());
+        assert!(unannotated.is_destructive());
+
+        let read_only = Tool::new("ls", "List files", schema.clone())
+            .with_annotations(ToolAnnotations::read_only());
+        assert!(read_only.is_read_only());
+        assert!(!read_only.is_destructive());
+
+        let append = Tool::new("log", "Append to the log", schema)
+            .with_title("Append log")
+            .with_annotations(ToolAnnotations::default().with_destructive(false));
+        assert!(!append.is_destructive());
+        assert_eq!(
+            serde_json::to_value(&append).unwrap(),
+            json!({
+                "name": "log",
+                "title": "Append log",
+                "description": "Append to the log",
+                "inputSchema": {"type": "object"},
+                "annotations": {"destructiveHint": false}
+            })
+        );
+    }
+}
@@ -0,0 +1,422 @@
+/// URI templates as defined by RFC 6570, used to match resource URIs against the
+/// templates a server offers and extract the values of their variables
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// A parsed URI template such as `file:///logs/{date}{?level,limit}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UriTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Expression { operator: Operator, vars: Vec<Var> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    /// `{var}`, comma separated values with reserved characters encoded
+    Simple,
+    /// `{+var}`, values may contain reserved characters such as `/`
+    Reserved,
+    /// `{#var}`, like `+` but prefixed by `#`
+    Fragment,
+    /// `{.var}`, each value prefixed by `.`
+    Label,
+    /// `{/var}`, each value a path segment
+    Path,
+    /// `{;var}`, each value a `;name=value` path parameter
+    PathParam,
+    /// `{?var}`, a query string starting with `?`
+    Query,
+    /// `{&var}`, continues a query string with `&`
+    QueryContinuation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Var {
+    name: String,
+    explode: bool,
+}
+
+impl Operator {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '+' => Some(Self::Reserved),
+            '#' => Some(Self::Fragment),
+            '.' => Some(Self::Label),
+            '/' => Some(Self::Path),
+            ';' => Some(Self::PathParam),
+            '?' => Some(Self::Query),
+            '&' => Some(Self::QueryContinuation),
+            _ => None,
+        }
+    }
+
+    /// The character the expansion starts with, if any
+    fn prefix(self) -> Option<char> {
+        match self {
+            Self::Simple | Self::Reserved => None,
+            Self::Fragment => Some('#'),
+            Self::Label => Some('.'),
+            Self::Path => Some('/'),
+            Self::PathParam => Some(';'),
+            Self::Query => Some('?'),
+            Self::QueryContinuation => Some('&'),
+        }
+    }
+
+    /// The character between the values of a multi-variable expression
+    fn separator(self) -> char {
+        match self {
+            Self::Simple | Self::Reserved | Self::Fragment => ',',
+            Self::Label => '.',
+            Self::Path => '/',
+            Self::PathParam => ';',
+            Self::Query | Self::QueryContinuation => '&',
+        }
+    }
+
+    /// Whether values may contain reserved characters unencoded
+    fn allows_reserved(self) -> bool {
+        matches!(self, Self::Reserved | Self::Fragment)
+    }
+}
+
+impl UriTemplate {
+    /// Parse a URI template, failing on unclosed or malformed expressions
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| anyhow!("Unclosed expression in URI template: {}", template))?;
+            parts.push(Self::parse_expression(&rest[start + 1..end], template)?);
+            rest = &rest[end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(anyhow!("Unopened expression in URI template: {}", template));
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            parts,
+        })
+    }
+
+    fn parse_expression(expression: &str, template: &str) -> Result<Part> {
+        let mut chars = expression.chars();
+        let (operator, vars) = match chars.next().and_then(Operator::from_char) {
+            Some(operator) => (operator, chars.as_str()),
+            None => (Operator::Simple, expression),
+        };
+
+        let vars = vars
+            .split(',')
+            .map(|spec| {
+                let (spec, explode) = match spec.strip_suffix('*') {
+                    Some(spec) => (spec, true),
+                    None => (spec, false),
+                };
+                // A prefix modifier only limits expansion, any length matches
+                let name = spec.split(':').next().unwrap_or_default();
+                let valid = !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '%');
+                if !valid {
+                    return Err(anyhow!(
+                        "Invalid variable '{}' in URI template: {}",
+                        spec,
+                        template
+                    ));
+                }
+                Ok(Var {
+                    name: name.to_string(),
+                    explode,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Part::Expression { operator, vars })
+    }
+
+    /// The template as written
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// The names of all variables in the template, in order
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.parts
+            .iter()
+            .flat_map(|part| match part {
+                Part::Literal(_) => [].iter(),
+                Part::Expression { vars, .. } => vars.iter(),
+            })
+            .map(|var| var.name.as_str())
+    }
+
+    /// Match `uri` against the template, returning the percent-decoded value of each
+    /// variable on success. Variables that the URI leaves out, such as absent query
+    /// parameters, are missing from the result.
+    pub fn match_uri(&self, uri: &str) -> Option<HashMap<String, String>> {
+        let mut variables = HashMap::new();
+        match_parts(&self.parts, uri, &mut variables).then_some(variables)
+    }
+}
+
+impl std::fmt::Display for UriTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+impl std::str::FromStr for UriTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+/// Match the remaining template parts against the remaining input, backtracking over the
+/// possible extent of each expression
+fn match_parts(parts: &[Part], input: &str, variables: &mut HashMap<String, String>) -> bool {
+    let Some((part, rest)) = parts.split_first() else {
+        return input.is_empty();
+    };
+
+    match part {
+        Part::Literal(literal) => input
+            .strip_prefix(literal.as_str())
+            .is_some_and(|input| match_parts(rest, input, variables)),
+        Part::Expression { operator, vars } => {
+            // Reserved and exploded expansions such as `{+path}` take as much as they
+            // can, others as little, so that `{name}{.ext}` splits at the last dot
+            let mut candidates: Vec<usize> = input
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain(std::iter::once(input.len()))
+                .collect();
+            if operator.allows_reserved() || vars.iter().any(|var| var.explode) {
+                candidates.reverse();
+            }
+            for end in candidates {
+                let (value, remaining) = input.split_at(end);
+                let Some(matched) = match_expression(*operator, vars, value) else {
+                    continue;
+                };
+                let mut attempt = variables.clone();
+                attempt.extend(matched);
+                if match_parts(rest, remaining, &mut attempt) {
+                    *variables = attempt;
+                    return true;
+                }
+            }
+            false
+        }
+    }
+}
+
+/// Match the text of a single expansion, returning its variables
+fn match_expression(operator: Operator, vars: &[Var], text: &str) -> Option<Vec<(String, String)>> {
+    // Undefined variables expand to nothing, prefix included
+    if text.is_empty() {
+        return Some(Vec::new());
+    }
+    let allowed = |c: char| {
+        is_unreserved(c)
+            || c == '%'
+            || c == operator.separator()
+            || (operator.allows_reserved() && is_reserved(c))
+            || (matches!(
+                operator,
+                Operator::PathParam | Operator::Query | Operator::QueryContinuation
+            ) && c == '=')
+            || (vars.iter().any(|v| v.explode) && c == ',')
+    };
+
+    let body = match operator.prefix() {
+        Some(prefix) => text.strip_prefix(prefix)?,
+        None => text,
+    };
+    if !body.chars().all(allowed) {
+        return None;
+    }
+
+    match operator {
+        Operator::Query | Operator::QueryContinuation | Operator::PathParam => {
+            // Named pairs, which may appear in any order
+            let mut matched = Vec::new();
+            for pair in body.split(operator.separator()) {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                let var = vars.iter().find(|var| var.name == name)?;
+                matched.push((var.name.clone(), percent_decode(value)?));
+            }
+            Some(matched)
+        }
+        _ => {
+            let separator = operator.separator();
+            let values: Vec<&str> = match vars {
+                // A single exploded variable takes every value, e.g. all path segments
+                [var] if var.explode => vec![body],
+                _ => body.splitn(vars.len(), separator).collect(),
+            };
+            if values.len() > vars.len() {
+                return None;
+            }
+            vars.iter()
+                .zip(values)
+                .map(|(var, value)| {
+                    if !operator.allows_reserved() && !var.explode && value.contains(separator) {
+                        return None;
+                    }
+                    Some((var.name.clone(), percent_decode(value)?))
+                })
+                .collect()
+        }
+    }
+}
+
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')
+}
+
+fn is_reserved(c: char) -> bool {
+    matches!(
+        c,
+        ':' | '/'
+            | '?'
+            | '#'
+            | '['
+            | ']'
+            | '@'
+            | '!'
+            | '$'
+            | '&'
+            | '\''
+            | '('
+            | ')'
+            | '*'
+            | '+'
+            | ','
+            | ';'
+            | '='
+    )
+}
+
+/// Decode `%XX` escapes, failing on malformed escapes or invalid UTF-8
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_simple_expansion() {
+        let template = UriTemplate::parse("users://{id}/profile").unwrap();
+        assert_eq!(
+            template.match_uri("users://42/profile"),
+            Some(vars(&[("id", "42")]))
+        );
+        assert_eq!(
+            template.match_uri("users://jane%20doe/profile"),
+            Some(vars(&[("id", "jane doe")]))
+        );
+        // Simple values can't span path segments
+        assert_eq!(template.match_uri("users://a/b/profile"), None);
+        assert_eq!(template.match_uri("users://42/settings"), None);
+    }
+
+    #[test]
+    fn test_reserved_and_path_expansion() {
+        let template = UriTemplate::parse("file:///{+path}").unwrap();
+        assert_eq!(
+            template.match_uri("file:///src/lib.rs"),
+            Some(vars(&[("path", "src/lib.rs")]))
+        );
+
+        let template = UriTemplate::parse("repo://{owner}{/repo,branch}").unwrap();
+        assert_eq!(
+            template.match_uri("repo://acme/widgets/main"),
+            Some(vars(&[
+                ("owner", "acme"),
+                ("repo", "widgets"),
+                ("branch", "main")
+            ]))
+        );
+
+        let template = UriTemplate::parse("docs://{name}{.ext}").unwrap();
+        assert_eq!(
+            template.match_uri("docs://readme.md"),
+            Some(vars(&[("name", "readme"), ("ext", "md")]))
+        );
+    }
+
+    #[test]
+    fn test_query_expansion() {
+        let template = UriTemplate::parse("logs://{date}{?level,limit}").unwrap();
+        assert_eq!(
+            template.match_uri("logs://2024-01-01?limit=10&level=warn"),
+            Some(vars(&[
+                ("date", "2024-01-01"),
+                ("level", "warn"),
+                ("limit", "10")
+            ]))
+        );
+        assert_eq!(
+            template.match_uri("logs://2024-01-01"),
+            Some(vars(&[("date", "2024-01-01")]))
+        );
+        assert_eq!(template.match_uri("logs://2024-01-01?other=1"), None);
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(UriTemplate::parse("file:///{path").is_err());
+        assert!(UriTemplate::parse("file:///path}").is_err());
+        assert!(UriTemplate::parse("file:///{}").is_err());
+        assert_eq!(
+            UriTemplate::parse("a://{x}/{+y}{?z}")
+                .unwrap()
+                .variables()
+                .collect::<Vec<_>>(),
+            vec!["x", "y", "z"]
+        );
+    }
+}
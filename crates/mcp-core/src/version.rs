@@ -0,0 +1,125 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A revision of the MCP specification, named after its release date.
+///
+/// The client proposes a version in its `initialize` request and the server answers with
+/// the version the connection will use. Versions compare by release date, so gates on
+/// newer message shapes can be written as `version >= ProtocolVersion::V2025_03_26`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    V2024_11_05,
+    V2025_03_26,
+    V2025_06_18,
+}
+
+/// The protocol versions this implementation supports, oldest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[ProtocolVersion] = &[
+    ProtocolVersion::V2024_11_05,
+    ProtocolVersion::V2025_03_26,
+    ProtocolVersion::V2025_06_18,
+];
+
+impl ProtocolVersion {
+    /// The newest supported version, proposed by clients and used by servers when the
+    /// client asks for a version they don't know
+    pub const LATEST: Self = ProtocolVersion::V2025_06_18;
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProtocolVersion::V2024_11_05 => "2024-11-05",
+            ProtocolVersion::V2025_03_26 => "2025-03-26",
+            ProtocolVersion::V2025_06_18 => "2025-06-18",
+        }
+    }
+
+    /// Pick the version a server answers with: the one the client asked for if it is
+    /// supported, otherwise the latest. The client then decides whether it can use it.
+    pub fn negotiate(requested: &str) -> Self {
+        requested.parse().unwrap_or(Self::LATEST)
+    }
+
+    /// Whether several messages may be sent as one JSON-RPC batch, which only 2025-03-26
+    /// allowed
+    pub fn supports_batching(self) -> bool {
+        self == ProtocolVersion::V2025_03_26
+    }
+
+    /// Whether progress notifications may carry a `message`
+    pub fn supports_progress_message(self) -> bool {
+        self >= ProtocolVersion::V2025_03_26
+    }
+
+    /// Whether servers may ask the user for input with `elicitation/create`
+    pub fn supports_elicitation(self) -> bool {
+        self >= ProtocolVersion::V2025_06_18
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error for a protocol version this implementation doesn't support
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Unsupported protocol version: {0}")]
+pub struct UnsupportedVersion(pub String);
+
+impl FromStr for ProtocolVersion {
+    type Err = UnsupportedVersion;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SUPPORTED_PROTOCOL_VERSIONS
+            .iter()
+            .copied()
+            .find(|version| version.as_str() == s)
+            .ok_or_else(|| UnsupportedVersion(s.to_string()))
+    }
+}
+
+impl Serialize for ProtocolVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtocolVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version = String::deserialize(deserializer)?;
+        version.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(
+            ProtocolVersion::negotiate("2024-11-05"),
+            ProtocolVersion::V2024_11_05
+        );
+        assert_eq!(ProtocolVersion::negotiate("1.0.0"), ProtocolVersion::LATEST);
+        assert_eq!(
+            "2023-01-01".parse::<ProtocolVersion>(),
+            Err(UnsupportedVersion("2023-01-01".to_string()))
+        );
+        assert!(SUPPORTED_PROTOCOL_VERSIONS.is_sorted());
+        assert_eq!(
+            SUPPORTED_PROTOCOL_VERSIONS.last(),
+            Some(&ProtocolVersion::LATEST)
+        );
+    }
+
+    #[test]
+    fn test_batching_only_in_2025_03_26() {
+        assert!(!ProtocolVersion::V2024_11_05.supports_batching());
+        assert!(ProtocolVersion::V2025_03_26.supports_batching());
+        assert!(!ProtocolVersion::V2025_06_18.supports_batching());
+    }
+}
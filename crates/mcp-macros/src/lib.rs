@@ -4,19 +4,34 @@ use quote::{format_ident, quote};
 use std::collections::HashMap;
 use syn::{
     parse::Parse, parse::ParseStream, parse_macro_input, punctuated::Punctuated, Expr, ExprLit,
-    FnArg, ItemFn, Lit, Meta, Pat, PatType, Token,
+    FnArg, GenericArgument, ItemFn, Lit, Meta, Pat, PatType, PathArguments, ReturnType, Token,
+    Type,
 };
 
 struct MacroArgs {
     name: Option<String>,
     description: Option<String>,
+    title: Option<String>,
+    hints: HashMap<String, bool>,
+    structured_output: bool,
     param_descriptions: HashMap<String, String>,
 }
 
+/// The annotation hints the macro accepts, with the `ToolAnnotations` field they set
+const HINTS: [(&str, &str); 4] = [
+    ("read_only", "read_only_hint"),
+    ("destructive", "destructive_hint"),
+    ("idempotent", "idempotent_hint"),
+    ("open_world", "open_world_hint"),
+];
+
 impl Parse for MacroArgs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut name = None;
         let mut description = None;
+        let mut title = None;
+        let mut hints = HashMap::new();
+        let mut structured_output = false;
         let mut param_descriptions = HashMap::new();
 
         let meta_list: Punctuated<Meta, Token![,]> = Punctuated::parse_terminated(input)?;
@@ -25,16 +40,37 @@ impl Parse for MacroArgs {
             match meta {
                 Meta::NameValue(nv) => {
                     let ident = nv.path.get_ident().unwrap().to_string();
-                    if let Expr::Lit(ExprLit {
-                        lit: Lit::Str(lit_str),
-                        ..
-                    }) = nv.value
-                    {
-                        match ident.as_str() {
+                    match nv.value {
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(lit_str),
+                            ..
+                        }) => match ident.as_str() {
                             "name" => name = Some(lit_str.value()),
                             "description" => description = Some(lit_str.value()),
+                            "title" => title = Some(lit_str.value()),
                             _ => {}
+                        },
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Bool(lit_bool),
+                            ..
+                        }) if ident == "structured_output" => structured_output = lit_bool.value,
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Bool(lit_bool),
+                            ..
+                        }) => {
+                            // Hints may also be spelled like the `ToolAnnotations` fields
+                            let Some((hint, _)) = HINTS
+                                .iter()
+                                .find(|(hint, field)| *hint == ident || *field == ident)
+                            else {
+                                return Err(syn::Error::new_spanned(
+                                    nv.path,
+                                    "expected one of read_only, destructive, idempotent or open_world",
+                                ));
+                            };
+                            hints.insert(hint.to_string(), lit_bool.value);
                         }
+                        _ => {}
                     }
                 }
                 Meta::List(list) if list.path.is_ident("params") => {
@@ -61,6 +97,9 @@ impl Parse for MacroArgs {
         Ok(MacroArgs {
             name,
             description,
+            title,
+            hints,
+            structured_output,
             param_descriptions,
         })
     }
@@ -81,6 +120,49 @@ pub fn tool(args: TokenStream, input: TokenStream) -> TokenStream {
     // Use provided name or function name as default
     let tool_name = args.name.unwrap_or(fn_name_str);
     let tool_description = args.description.unwrap_or_default();
+    let tool_title = match &args.title {
+        Some(title) => quote! { Some(#title) },
+        None => quote! { None },
+    };
+
+    // Annotations are only declared if at least one hint or the title is given
+    let tool_annotations = if args.hints.is_empty() && args.title.is_none() {
+        quote! { None }
+    } else {
+        let hint_fields = HINTS.iter().map(|(hint, field)| {
+            let field = format_ident!("{}", field);
+            match args.hints.get(*hint) {
+                Some(value) => quote! { #field: Some(#value) },
+                None => quote! { #field: None },
+            }
+        });
+        quote! {
+            Some(mcp_core::tool::ToolAnnotations {
+                title: #tool_title.map(str::to_string),
+                #(#hint_fields,)*
+            })
+        }
+    };
+
+    // Structured output is described by the `T` of the function's `Result<T, E>`
+    let output_schema = if args.structured_output {
+        let Some(output_ty) = result_ok_type(&input_fn.sig.output) else {
+            return syn::Error::new_spanned(
+                &input_fn.sig,
+                "structured_output requires the tool to return Result<T, E>",
+            )
+            .to_compile_error()
+            .into();
+        };
+        quote! {
+            Some(
+                mcp_core::handler::generate_output_schema::<#output_ty>()
+                    .expect("Failed to generate output schema"),
+            )
+        }
+    } else {
+        quote! { None }
+    };
 
     // Extract parameter names, types, and descriptions
     let mut param_defs = Vec::new();
@@ -129,11 +211,23 @@ pub fn tool(args: TokenStream, input: TokenStream) -> TokenStream {
                 #tool_description
             }
 
+            fn title(&self) -> Option<&'static str> {
+                #tool_title
+            }
+
+            fn annotations(&self) -> Option<mcp_core::tool::ToolAnnotations> {
+                #tool_annotations
+            }
+
             fn schema(&self) -> serde_json::Value {
                 mcp_core::handler::generate_schema::<#params_struct_name>()
                     .expect("Failed to generate schema")
             }
 
+            fn output_schema(&self) -> Option<serde_json::Value> {
+                #output_schema
+            }
+
             async fn call(&self, params: serde_json::Value) -> Result<serde_json::Value, mcp_core::handler::ToolError> {
                 let params: #params_struct_name = serde_json::from_value(params)
                     .map_err(|e| mcp_core::handler::ToolError::InvalidParameters(e.to_string()))?;
@@ -150,3 +244,24 @@ pub fn tool(args: TokenStream, input: TokenStream) -> TokenStream {
 
     TokenStream::from(expanded)
 }
+
+/// The `T` of a function returning `Result<T, E>`
+fn result_ok_type(output: &ReturnType) -> Option<&Type> {
+    let ReturnType::Type(_, ty) = output else {
+        return None;
+    };
+    let Type::Path(path) = &**ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
@@ -0,0 +1,89 @@
+use mcp_core::handler::{ToolError, ToolHandler};
+use mcp_core::protocol::CallToolResult;
+use mcp_core::tool::ToolAnnotations;
+use mcp_macros::tool;
+use serde_json::json;
+
+#[tool(
+    name = "delete_file",
+    description = "Delete a file",
+    title = "Delete file",
+    read_only_hint = false,
+    destructive_hint = true,
+    idempotent = true,
+    params(path = "The file to delete")
+)]
+async fn delete_file(path: String) -> Result<String, ToolError> {
+    Ok(format!("deleted {}", path))
+}
+
+#[tool(name = "echo", description = "Echo the input")]
+async fn echo(text: String) -> Result<String, ToolError> {
+    Ok(text)
+}
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+struct WordCount {
+    words: usize,
+    longest: Option<String>,
+}
+
+#[tool(
+    name = "count_words",
+    description = "Count the words of a text",
+    structured_output = true,
+    params(text = "The text to count")
+)]
+async fn count_words(text: String) -> Result<WordCount, ToolError> {
+    Ok(WordCount {
+        words: text.split_whitespace().count(),
+        longest: text
+            .split_whitespace()
+            .max_by_key(|word| word.len())
+            .map(str::to_string),
+    })
+}
+
+#[test]
+fn test_tool_annotations() {
+    let tool = DeleteFile.tool();
+    assert_eq!(tool.name, "delete_file");
+    assert_eq!(tool.title.as_deref(), Some("Delete file"));
+    assert_eq!(
+        tool.annotations,
+        Some(ToolAnnotations {
+            title: Some("Delete file".to_string()),
+            read_only_hint: Some(false),
+            destructive_hint: Some(true),
+            idempotent_hint: Some(true),
+            open_world_hint: None,
+        })
+    );
+
+    // Without hints or a title the tool declares no annotations
+    let tool = Echo.tool();
+    assert_eq!(tool.title, None);
+    assert_eq!(tool.annotations, None);
+}
+
+#[tokio::test]
+async fn test_structured_output() {
+    let output_schema = CountWords.output_schema().unwrap();
+    assert_eq!(output_schema["type"], "object");
+    assert_eq!(output_schema["properties"]["words"]["type"], "integer");
+    assert_eq!(output_schema["required"], json!(["words"]));
+    assert_eq!(CountWords.tool().output_schema, Some(output_schema));
+
+    let value = CountWords
+        .call(json!({ "text": "a structured tool" }))
+        .await
+        .unwrap();
+    let result = CallToolResult::structured(value);
+    assert_eq!(
+        result.structured_content,
+        Some(json!({ "words": 3, "longest": "structured" }))
+    );
+
+    // Tools without structured output declare no output schema
+    assert_eq!(Echo.output_schema(), None);
+}
@@ -18,6 +18,9 @@ pub enum TransportError {
 
     #[error("Invalid message format: {0}")]
     InvalidMessage(String),
+
+    #[error("Frame exceeds the maximum size of {0} bytes")]
+    FrameTooLarge(usize),
 }
 
 #[derive(Error, Debug)]
@@ -35,6 +38,24 @@ pub enum ServerError {
     Timeout(#[from] tower::timeout::error::Elapsed),
 }
 
+#[derive(Error, Debug)]
+pub enum PeerError {
+    #[error("Connection to the client is closed")]
+    Closed,
+
+    #[error("Client does not support {0}")]
+    Unsupported(&'static str),
+
+    #[error("Invalid response from the client: {0}")]
+    InvalidResponse(String),
+
+    #[error("RPC error: code={code}, message={message}")]
+    RpcError { code: i32, message: String },
+
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
 #[derive(Error, Debug)]
 pub enum RouterError {
     #[error("Method not found: {0}")]
@@ -1,251 +1,513 @@
 use std::{
+    collections::{HashMap, VecDeque},
     pin::Pin,
-    task::{Context, Poll},
 };
 
-use futures::{Future, Stream};
-use mcp_core::protocol::{JsonRpcError, JsonRpcMessage, JsonRpcRequest, JsonRpcResponse};
-use pin_project::pin_project;
-use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use futures::{Future, SinkExt, StreamExt};
+use mcp_core::protocol::{
+    CancelledNotification, ErrorData, JsonRpcError, JsonRpcMessage, JsonRpcNotification,
+    JsonRpcRequest, JsonRpcResponse, RequestId, RootsListChangedNotification, INTERNAL_ERROR,
+    INVALID_REQUEST, PARSE_ERROR,
+};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use tower_service::Service;
+use tracing::Instrument;
 
 mod errors;
-pub use errors::{BoxError, RouterError, ServerError, TransportError};
+pub use errors::{BoxError, PeerError, RouterError, ServerError, TransportError};
 
-pub mod router;
-pub use router::Router;
+pub mod list_changed;
+pub use list_changed::ListChangedNotifier;
 
-/// A transport layer that handles JSON-RPC messages over byte
-#[pin_project]
-pub struct ByteTransport<R, W> {
-    // Reader is a BufReader on the underlying stream (stdin or similar) buffering
-    // the underlying data across poll calls, we clear one line (\n) during each
-    // iteration of poll_next from this buffer
-    #[pin]
-    reader: BufReader<R>,
-    #[pin]
-    writer: W,
-}
+pub mod logging;
 
-impl<R, W> ByteTransport<R, W>
-where
-    R: AsyncRead,
-    W: AsyncWrite,
-{
-    pub fn new(reader: R, writer: W) -> Self {
-        Self {
-            // Default BufReader capacity is 8 * 1024, increase this to 2MB to the file size limit
-            // allows the buffer to have the capacity to read very large calls
-            reader: BufReader::with_capacity(2 * 1024 * 1024, reader),
-            writer,
-        }
-    }
-}
+pub mod output_schemas;
+pub use output_schemas::OutputSchemas;
 
-impl<R, W> Stream for ByteTransport<R, W>
-where
-    R: AsyncRead + Unpin,
-    W: AsyncWrite + Unpin,
-{
-    type Item = Result<JsonRpcMessage, TransportError>;
-
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let mut this = self.project();
-        let mut buf = Vec::new();
-
-        let mut reader = this.reader.as_mut();
-        let mut read_future = Box::pin(reader.read_until(b'\n', &mut buf));
-        match read_future.as_mut().poll(cx) {
-            Poll::Ready(Ok(0)) => Poll::Ready(None), // EOF
-            Poll::Ready(Ok(_)) => {
-                // Convert to UTF-8 string
-                let line = match String::from_utf8(buf) {
-                    Ok(s) => s,
-                    Err(e) => return Poll::Ready(Some(Err(TransportError::Utf8(e)))),
-                };
-                // Log incoming message here before serde conversion to
-                // track incomplete chunks which are not valid JSON
-                tracing::info!(json = %line, "incoming message");
-
-                // Parse JSON and validate message format
-                match serde_json::from_str::<serde_json::Value>(&line) {
-                    Ok(value) => {
-                        // Validate basic JSON-RPC structure
-                        if !value.is_object() {
-                            return Poll::Ready(Some(Err(TransportError::InvalidMessage(
-                                "Message must be a JSON object".into(),
-                            ))));
-                        }
-                        let obj = value.as_object().unwrap(); // Safe due to check above
+pub mod pagination;
 
-                        // Check jsonrpc version field
-                        if !obj.contains_key("jsonrpc") || obj["jsonrpc"] != "2.0" {
-                            return Poll::Ready(Some(Err(TransportError::InvalidMessage(
-                                "Missing or invalid jsonrpc version".into(),
-                            ))));
-                        }
+pub mod peer;
+pub use peer::{Peer, ProgressReporter, RequestContext};
 
-                        // Now try to parse as proper message
-                        match serde_json::from_value::<JsonRpcMessage>(value) {
-                            Ok(msg) => Poll::Ready(Some(Ok(msg))),
-                            Err(e) => Poll::Ready(Some(Err(TransportError::Json(e)))),
-                        }
-                    }
-                    Err(e) => Poll::Ready(Some(Err(TransportError::Json(e)))),
-                }
-            }
-            Poll::Ready(Err(e)) => Poll::Ready(Some(Err(TransportError::Io(e)))),
-            Poll::Pending => Poll::Pending,
-        }
-    }
-}
+pub mod prompt_templates;
+pub use prompt_templates::PromptTemplates;
 
-impl<R, W> ByteTransport<R, W>
-where
-    R: AsyncRead + Unpin,
-    W: AsyncWrite + Unpin,
-{
-    pub async fn write_message(
-        self: &mut Pin<&mut Self>,
-        msg: JsonRpcMessage,
-    ) -> Result<(), std::io::Error> {
-        let json = serde_json::to_string(&msg)?;
-
-        let mut this = self.as_mut().project();
-        this.writer.write_all(json.as_bytes()).await?;
-        this.writer.write_all(b"\n").await?;
-        this.writer.flush().await?;
-
-        Ok(())
-    }
-}
+pub mod router;
+pub use router::Router;
+
+pub mod subscriptions;
+pub use subscriptions::ResourceSubscriptions;
+
+pub mod transport;
+pub use transport::{ByteTransport, JsonRpcFrameCodec, ServerTransport};
+
+/// Default number of requests the server works on at the same time
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 32;
+
+/// Error code for a request turned away because the server has too many queued already
+pub const SERVER_BUSY: i32 = -32000;
 
 /// The main server type that processes incoming requests
 pub struct Server<S> {
     service: S,
+    max_concurrent_requests: usize,
+    peer: Peer,
+    outgoing: mpsc::UnboundedReceiver<JsonRpcMessage>,
+}
+
+/// A request waiting for the service to become ready, with the batch it belongs to if any
+type QueuedRequest = (Option<u64>, JsonRpcRequest);
+
+/// A request being processed, resolving to the batch it belongs to if any, its id, and
+/// its response unless it was cancelled
+type InFlight =
+    Pin<Box<dyn Future<Output = (Option<u64>, Option<RequestId>, Option<JsonRpcResponse>)> + Send>>;
+
+/// Responses collected so far for a batch, and how many are still outstanding
+struct PendingBatch {
+    remaining: usize,
+    responses: Vec<JsonRpcMessage>,
 }
 
 impl<S> Server<S>
 where
     S: Service<JsonRpcRequest, Response = JsonRpcResponse> + Send,
-    S::Error: Into<BoxError>,
-    S::Future: Send,
+    S::Error: Into<BoxError> + 'static,
+    S::Future: Send + 'static,
 {
     pub fn new(service: S) -> Self {
-        Self { service }
+        let (peer, outgoing) = Peer::new();
+        Self {
+            service,
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            peer,
+            outgoing,
+        }
     }
 
-    // TODO transport trait instead of byte transport if we implement others
-    pub async fn run<R, W>(self, mut transport: ByteTransport<R, W>) -> Result<(), ServerError>
+    /// A handle to the client this server will be connected to once it runs. Messages sent
+    /// through it before [`Server::run`] is called are delivered when the server starts.
+    pub fn peer(&self) -> Peer {
+        self.peer.clone()
+    }
+
+    /// Set how many requests may be processed at the same time. Once the limit is reached
+    /// further requests are queued, and once as many are queued the next ones are answered
+    /// with a [`SERVER_BUSY`] error until a request completes. Responses and notifications
+    /// from the client are always read, since a running handler may be waiting on them.
+    ///
+    /// # Panics
+    /// Panics if `max` is zero
+    pub fn with_max_concurrent_requests(mut self, max: usize) -> Self {
+        assert!(max > 0, "max_concurrent_requests must be at least 1");
+        self.max_concurrent_requests = max;
+        self
+    }
+
+    /// Serve requests arriving on `transport` until the peer closes it
+    pub async fn run<T>(self, transport: T) -> Result<(), ServerError>
     where
-        R: AsyncRead + Unpin,
-        W: AsyncWrite + Unpin,
+        T: ServerTransport,
     {
-        use futures::StreamExt;
-        let mut service = self.service;
-        let mut transport = Pin::new(&mut transport);
+        use futures::stream::FuturesUnordered;
+        let Self {
+            mut service,
+            max_concurrent_requests: max_in_flight,
+            peer,
+            mut outgoing,
+        } = self;
+        let mut transport = std::pin::pin!(transport);
+
+        // Requests are dispatched concurrently and their responses written as soon as they
+        // complete, so responses can go out in a different order than requests came in
+        let mut in_flight: FuturesUnordered<InFlight> = FuturesUnordered::new();
+        let mut queue: VecDeque<QueuedRequest> = VecDeque::new();
+        let mut batches: HashMap<u64, PendingBatch> = HashMap::new();
+        let mut next_batch_id = 0u64;
+        // Requests that are queued or in flight and may still be cancelled by the client
+        let mut cancellations: HashMap<RequestId, CancellationToken> = HashMap::new();
+        let mut reading = true;
 
         tracing::info!("Server started");
-        while let Some(msg_result) = transport.next().await {
-            let _span = tracing::span!(tracing::Level::INFO, "message_processing");
-            let _enter = _span.enter();
-            match msg_result {
-                Ok(msg) => {
-                    match msg {
-                        JsonRpcMessage::Request(request) => {
-                            // Serialize request for logging
-                            let id = request.id;
-                            let request_json = serde_json::to_string(&request)
-                                .unwrap_or_else(|_| "Failed to serialize request".to_string());
-
-                            tracing::info!(
-                                request_id = ?id,
-                                method = ?request.method,
-                                json = %request_json,
-                                "Received request"
-                            );
+        let result = loop {
+            let has_capacity = in_flight.len() < max_in_flight;
+            // As many requests may wait for a slot as can be processed at once, counting
+            // the ones queued while a slot is still free
+            let queue_room = (2 * max_in_flight).saturating_sub(queue.len() + in_flight.len());
+            if !reading && queue.is_empty() && in_flight.is_empty() {
+                break Ok(());
+            }
 
-                            // Process the request using our service
-                            let response = match service.call(request).await {
-                                Ok(resp) => resp,
-                                Err(e) => {
-                                    let error_msg = e.into().to_string();
-                                    tracing::error!(error = %error_msg, "Request processing failed");
-                                    JsonRpcResponse {
-                                        jsonrpc: "2.0".to_string(),
-                                        id,
-                                        result: None,
-                                        error: Some(mcp_core::protocol::ErrorData {
-                                            code: mcp_core::protocol::INTERNAL_ERROR,
-                                            message: error_msg,
-                                            data: None,
-                                        }),
-                                    }
-                                }
-                            };
+            tokio::select! {
+                // Requests and notifications the server sends to the client through its peer
+                Some(message) = outgoing.recv() => {
+                    if let Err(e) = transport.send(message).await {
+                        break Err(e.into());
+                    }
+                }
 
-                            // Serialize response for logging
-                            let response_json = serde_json::to_string(&response)
-                                .unwrap_or_else(|_| "Failed to serialize response".to_string());
+                Some((batch, id, response)) = in_flight.next(), if !in_flight.is_empty() => {
+                    if let Some(id) = &id {
+                        cancellations.remove(id);
+                    }
 
-                            tracing::info!(
-                                response_id = ?response.id,
-                                json = %response_json,
-                                "Sending response"
-                            );
-                            // Send the response back
-                            if let Err(e) = transport
-                                .write_message(JsonRpcMessage::Response(response))
-                                .await
-                            {
-                                return Err(ServerError::Transport(TransportError::Io(e)));
+                    // Cancelled requests get no response, in a batch they are left out
+                    let message = match batch {
+                        None => match response {
+                            Some(response) => JsonRpcMessage::Response(response),
+                            None => continue,
+                        },
+                        Some(batch_id) => {
+                            let Some(pending) = batches.get_mut(&batch_id) else {
+                                continue;
+                            };
+                            pending
+                                .responses
+                                .extend(response.map(JsonRpcMessage::Response));
+                            pending.remaining -= 1;
+                            if pending.remaining > 0 {
+                                continue;
+                            }
+                            match batches.remove(&batch_id) {
+                                Some(pending) if !pending.responses.is_empty() => {
+                                    JsonRpcMessage::Batch(pending.responses)
+                                }
+                                _ => continue,
                             }
                         }
-                        JsonRpcMessage::Response(_)
-                        | JsonRpcMessage::Notification(_)
-                        | JsonRpcMessage::Nil
-                        | JsonRpcMessage::Error(_) => {
-                            // Ignore responses, notifications and nil messages for now
-                            continue;
-                        }
+                    };
+
+                    // Messages the handler sent to the client, such as progress, must go
+                    // out before the response that completes it
+                    let mut sent = Ok(());
+                    while let (Ok(()), Ok(queued)) = (&sent, outgoing.try_recv()) {
+                        sent = transport.send(queued).await;
                     }
+                    if let Err(e) = sent {
+                        break Err(e.into());
+                    }
+                    if let Err(e) = transport.send(message).await {
+                        break Err(e.into());
+                    }
+                }
+
+                // Honor backpressure from the service before handing it the next request
+                ready = futures::future::poll_fn(|cx| service.poll_ready(cx)),
+                    if !queue.is_empty() && has_capacity =>
+                {
+                    if let Err(e) = ready {
+                        break Err(ServerError::Service(e.into().to_string()));
+                    }
+                    let Some((batch, request)) = queue.pop_front() else {
+                        continue;
+                    };
+                    let id = request.id.clone();
+                    let cancellation = id
+                        .as_ref()
+                        .and_then(|id| cancellations.get(id).cloned())
+                        .unwrap_or_default();
+                    let context = RequestContext::new(peer.clone(), &request, cancellation.clone());
+                    let call = Self::process_request(&mut service, request, context);
+
+                    // Cancelling drops the handler's future, stopping its work
+                    in_flight.push(Box::pin(async move {
+                        let response = tokio::select! {
+                            biased;
+                            _ = cancellation.cancelled() => None,
+                            response = call => Some(response),
+                        };
+                        (batch, id, response)
+                    }));
                 }
-                Err(e) => {
-                    // Convert transport error to JSON-RPC error response
-                    let error = match e {
-                        TransportError::Json(_) | TransportError::InvalidMessage(_) => {
-                            mcp_core::protocol::ErrorData {
-                                code: mcp_core::protocol::PARSE_ERROR,
-                                message: e.to_string(),
-                                data: None,
+
+                // Always keep reading, the client may be about to answer a request that an
+                // in-flight handler is waiting on
+                msg_result = transport.next(), if reading => {
+                    match msg_result {
+                        None => reading = false,
+                        Some(Ok(JsonRpcMessage::Request(request))) => {
+                            if queue_room == 0 {
+                                if let Err(e) = transport.send(busy_error(request.id)).await {
+                                    break Err(e.into());
+                                }
+                                continue;
                             }
+                            track_cancellation(&mut cancellations, &request);
+                            queue.push_back((None, request));
                         }
-                        TransportError::Protocol(_) => mcp_core::protocol::ErrorData {
-                            code: mcp_core::protocol::INVALID_REQUEST,
-                            message: e.to_string(),
-                            data: None,
-                        },
-                        _ => mcp_core::protocol::ErrorData {
-                            code: mcp_core::protocol::INTERNAL_ERROR,
-                            message: e.to_string(),
-                            data: None,
-                        },
-                    };
+                        Some(Ok(JsonRpcMessage::Batch(messages))) => {
+                            tracing::info!(size = messages.len(), "Received batch");
+
+                            // Same as the client, a batch is fine until a version that
+                            // doesn't allow them is agreed on
+                            if let Some(version) =
+                                peer.protocol_version().filter(|v| !v.supports_batching())
+                            {
+                                let error = JsonRpcMessage::Error(JsonRpcError {
+                                    jsonrpc: "2.0".to_string(),
+                                    id: None,
+                                    error: ErrorData {
+                                        code: INVALID_REQUEST,
+                                        message: format!(
+                                            "Protocol version {} does not support batching",
+                                            version
+                                        ),
+                                        data: None,
+                                    },
+                                });
+                                if let Err(e) = transport.send(error).await {
+                                    break Err(e.into());
+                                }
+                                continue;
+                            }
 
-                    let error_response = JsonRpcMessage::Error(JsonRpcError {
-                        jsonrpc: "2.0".to_string(),
-                        id: None,
-                        error,
-                    });
+                            // Dispatch every request in the batch, notifications and other
+                            // messages get no entry in the batched reply, while invalid
+                            // elements and requests beyond the queue's room are answered with
+                            // their error right away
+                            let mut errors = Vec::new();
+                            let mut room = queue_room;
+                            let requests: Vec<_> = messages
+                                .into_iter()
+                                .filter_map(|message| match message {
+                                    JsonRpcMessage::Request(request) if room == 0 => {
+                                        errors.push(busy_error(request.id));
+                                        None
+                                    }
+                                    JsonRpcMessage::Request(request) => {
+                                        room -= 1;
+                                        track_cancellation(&mut cancellations, &request);
+                                        Some(request)
+                                    }
+                                    JsonRpcMessage::Notification(notification) => {
+                                        handle_notification(&peer, &cancellations, notification);
+                                        None
+                                    }
+                                    JsonRpcMessage::Invalid(error) => {
+                                        errors.push(JsonRpcMessage::Error(error));
+                                        None
+                                    }
+                                    other => {
+                                        peer.handle_response(other);
+                                        None
+                                    }
+                                })
+                                .collect();
 
-                    if let Err(e) = transport.write_message(error_response).await {
-                        return Err(ServerError::Transport(TransportError::Io(e)));
+                            // Without requests the reply only holds the errors, and a batch
+                            // consisting only of notifications gets no reply at all
+                            if requests.is_empty() {
+                                if !errors.is_empty() {
+                                    let reply = JsonRpcMessage::Batch(errors);
+                                    if let Err(e) = transport.send(reply).await {
+                                        break Err(e.into());
+                                    }
+                                }
+                                continue;
+                            }
+
+                            let batch_id = next_batch_id;
+                            next_batch_id += 1;
+                            let mut responses = errors;
+                            responses.reserve(requests.len());
+                            batches.insert(
+                                batch_id,
+                                PendingBatch {
+                                    remaining: requests.len(),
+                                    responses,
+                                },
+                            );
+                            queue.extend(requests.into_iter().map(|r| (Some(batch_id), r)));
+                        }
+                        Some(Ok(
+                            message @ (JsonRpcMessage::Response(_) | JsonRpcMessage::Error(_)),
+                        )) => {
+                            // Answers to requests the server sent through its peer
+                            if !peer.handle_response(message) {
+                                tracing::warn!("Received a response to an unknown request");
+                            }
+                        }
+                        Some(Ok(JsonRpcMessage::Notification(notification))) => {
+                            handle_notification(&peer, &cancellations, notification);
+                        }
+                        Some(Ok(JsonRpcMessage::Invalid(error))) => {
+                            if let Err(e) = transport.send(JsonRpcMessage::Error(error)).await {
+                                break Err(e.into());
+                            }
+                        }
+                        Some(Ok(JsonRpcMessage::Nil)) => continue,
+                        Some(Err(e)) => {
+                            // Convert transport error to JSON-RPC error response
+                            let error_response = JsonRpcMessage::Error(JsonRpcError {
+                                jsonrpc: "2.0".to_string(),
+                                id: None,
+                                error: transport_error_data(&e),
+                            });
+
+                            if let Err(e) = transport.send(error_response).await {
+                                break Err(e.into());
+                            }
+                        }
                     }
                 }
+
+                else => break Ok(()),
             }
+        };
+
+        // Requests still waiting on the client will never get an answer
+        peer.close();
+        result
+    }
+
+    /// Hand a single request to the service, returning a future that resolves to its
+    /// response. Service errors become JSON-RPC error responses so that the peer always
+    /// gets a reply.
+    fn process_request(
+        service: &mut S,
+        request: JsonRpcRequest,
+        context: RequestContext,
+    ) -> impl Future<Output = JsonRpcResponse> + Send + 'static {
+        // Serialize request for logging
+        let request_json = serde_json::to_string(&request)
+            .unwrap_or_else(|_| "Failed to serialize request".to_string());
+
+        tracing::info!(
+            request_id = ?request.id,
+            method = ?request.method,
+            json = %request_json,
+            "Received request"
+        );
+
+        let span = tracing::info_span!("message_processing", request_id = ?request.id);
+
+        // Process the request using our service
+        let id = request.id.clone();
+        // The context is set both while the service builds its future and while it runs
+        let call = context.sync_scope(|| service.call(request));
+        let call = context.scope(call_response(id, call));
+
+        async move {
+            let response = call.await;
+
+            // Serialize response for logging
+            let response_json = serde_json::to_string(&response)
+                .unwrap_or_else(|_| "Failed to serialize response".to_string());
+
+            tracing::info!(
+                response_id = ?response.id,
+                json = %response_json,
+                "Sending response"
+            );
+
+            response
         }
+        .instrument(span)
+    }
+}
+
+/// Remember a request so that the client can cancel it until it completes
+fn track_cancellation(
+    cancellations: &mut HashMap<RequestId, CancellationToken>,
+    request: &JsonRpcRequest,
+) {
+    if let Some(id) = &request.id {
+        cancellations.insert(id.clone(), CancellationToken::new());
+    }
+}
+
+/// Act on a notification from the client. Only cancellations and changes to the client's
+/// roots concern the server itself, other notifications are ignored for now.
+fn handle_notification(
+    peer: &Peer,
+    cancellations: &HashMap<RequestId, CancellationToken>,
+    notification: JsonRpcNotification,
+) {
+    if notification.method == RootsListChangedNotification::METHOD {
+        peer.notify_roots_changed();
+        return;
+    }
+    if notification.method != CancelledNotification::METHOD {
+        return;
+    }
+    let params = notification.params.unwrap_or_default();
+    match serde_json::from_value::<CancelledNotification>(params) {
+        Ok(cancelled) => match cancellations.get(&cancelled.request_id) {
+            Some(token) => {
+                tracing::info!(
+                    request_id = %cancelled.request_id,
+                    reason = ?cancelled.reason,
+                    "Request cancelled by the client"
+                );
+                token.cancel();
+            }
+            // The request may have completed already, which is expected to happen
+            None => tracing::debug!(
+                request_id = %cancelled.request_id,
+                "Cancellation for a request that is not running"
+            ),
+        },
+        Err(e) => tracing::warn!(error = %e, "Invalid cancellation notification"),
+    }
+}
+
+/// The error answering a request that didn't fit in the server's queue
+fn busy_error(id: Option<RequestId>) -> JsonRpcMessage {
+    JsonRpcMessage::Error(JsonRpcError {
+        jsonrpc: "2.0".to_string(),
+        id,
+        error: ErrorData {
+            code: SERVER_BUSY,
+            message: "Server is busy, try again later".to_string(),
+            data: None,
+        },
+    })
+}
 
-        Ok(())
+/// Map a transport error onto the JSON-RPC error reported back to the peer
+fn transport_error_data(e: &TransportError) -> ErrorData {
+    match e {
+        TransportError::Json(_) | TransportError::InvalidMessage(_) => ErrorData {
+            code: PARSE_ERROR,
+            message: e.to_string(),
+            data: None,
+        },
+        TransportError::Protocol(_) | TransportError::FrameTooLarge(_) => ErrorData {
+            code: INVALID_REQUEST,
+            message: e.to_string(),
+            data: None,
+        },
+        _ => ErrorData {
+            code: INTERNAL_ERROR,
+            message: e.to_string(),
+            data: None,
+        },
+    }
+}
+
+/// Await a service call, turning a service error into an internal error response for `id`
+async fn call_response<F, E>(id: Option<RequestId>, call: F) -> JsonRpcResponse
+where
+    F: Future<Output = Result<JsonRpcResponse, E>>,
+    E: Into<BoxError>,
+{
+    match call.await {
+        Ok(resp) => resp,
+        Err(e) => {
+            let error_msg = e.into().to_string();
+            tracing::error!(error = %error_msg, "Request processing failed");
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id,
+                result: None,
+                error: Some(ErrorData {
+                    code: INTERNAL_ERROR,
+                    message: error_msg,
+                    data: None,
+                }),
+            }
+        }
     }
 }
 
@@ -273,3 +535,542 @@ impl<T> BoundedService for T where
         + 'static
 {
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+    use tokio::io::{duplex, AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::sync::{mpsc, Notify};
+
+    /// Answers every request immediately, except `slow` which waits until released and
+    /// `ask` which forwards a request to the client and answers with its result, and
+    /// `index` which reports progress
+    #[derive(Clone)]
+    struct GatedService {
+        release: Arc<Notify>,
+    }
+
+    impl Service<JsonRpcRequest> for GatedService {
+        type Response = JsonRpcResponse;
+        type Error = BoxError;
+        type Future = Pin<Box<dyn Future<Output = Result<JsonRpcResponse, BoxError>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: JsonRpcRequest) -> Self::Future {
+            let release = self.release.clone();
+            let context = RequestContext::current();
+            Box::pin(async move {
+                let result = match req.method.as_str() {
+                    "slow" => {
+                        release.notified().await;
+                        serde_json::json!({ "method": req.method })
+                    }
+                    "ask" => {
+                        let peer = context.expect("request context").peer().clone();
+                        peer.send_notification("notifications/asking", serde_json::json!({}))?;
+                        peer.send_request("client/answer", serde_json::json!({}))
+                            .await?
+                    }
+                    "index" => {
+                        let progress = ProgressReporter::current();
+                        progress.report(1.0, Some(2.0), None);
+                        progress.report(2.0, Some(2.0), Some("done".to_string()));
+                        serde_json::json!({ "reported": progress.is_enabled() })
+                    }
+                    _ => serde_json::json!({ "method": req.method }),
+                };
+                Ok(JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: req.id,
+                    result: Some(result),
+                    error: None,
+                })
+            })
+        }
+    }
+
+    /// An in-memory transport over a pair of channels
+    struct ChannelTransport {
+        incoming: mpsc::UnboundedReceiver<JsonRpcMessage>,
+        outgoing: mpsc::UnboundedSender<JsonRpcMessage>,
+    }
+
+    impl futures::Stream for ChannelTransport {
+        type Item = Result<JsonRpcMessage, TransportError>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            self.incoming.poll_recv(cx).map(|msg| msg.map(Ok))
+        }
+    }
+
+    impl futures::Sink<JsonRpcMessage> for ChannelTransport {
+        type Error = TransportError;
+
+        fn poll_ready(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn start_send(self: Pin<&mut Self>, item: JsonRpcMessage) -> Result<(), Self::Error> {
+            self.outgoing
+                .send(item)
+                .map_err(|_| TransportError::Protocol("channel closed".into()))
+        }
+
+        fn poll_flush(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport() {
+        let (client_tx, incoming) = mpsc::unbounded_channel();
+        let (outgoing, mut client_rx) = mpsc::unbounded_channel();
+
+        let server = Server::new(GatedService {
+            release: Arc::new(Notify::new()),
+        });
+        let handle = tokio::spawn(server.run(ChannelTransport { incoming, outgoing }));
+
+        client_tx
+            .send(JsonRpcMessage::Request(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(RequestId::from("in-memory")),
+                method: "ping".to_string(),
+                params: None,
+            }))
+            .unwrap();
+
+        match client_rx.recv().await {
+            Some(JsonRpcMessage::Response(response)) => {
+                assert_eq!(response.id, Some(RequestId::from("in-memory")));
+            }
+            other => panic!("Expected Response, got {:?}", other),
+        }
+
+        drop(client_tx);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_does_not_block_others() {
+        let release = Arc::new(Notify::new());
+        let (client, server) = duplex(1024);
+        let (server_read, server_write) = tokio::io::split(server);
+        let (client_read, mut client_write) = tokio::io::split(client);
+
+        let server = Server::new(GatedService {
+            release: release.clone(),
+        });
+        let handle = tokio::spawn(server.run(ByteTransport::new(server_read, server_write)));
+
+        client_write
+            .write_all(
+                b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"slow\"}\n\
+                  {\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"ping\"}\n",
+            )
+            .await
+            .unwrap();
+
+        let mut lines = BufReader::new(client_read).lines();
+        let first: JsonRpcMessage =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert!(
+            matches!(first, JsonRpcMessage::Response(r) if r.id == Some(RequestId::Number(2.into())))
+        );
+
+        release.notify_one();
+        let second: JsonRpcMessage =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert!(
+            matches!(second, JsonRpcMessage::Response(r) if r.id == Some(RequestId::Number(1.into())))
+        );
+
+        // Closing the connection lets the server run to completion
+        drop(client_write);
+        drop(lines);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_requests_wait_for_capacity() {
+        let (client_tx, incoming) = mpsc::unbounded_channel();
+        let (outgoing, mut client_rx) = mpsc::unbounded_channel();
+
+        let release = Arc::new(Notify::new());
+        let server = Server::new(GatedService {
+            release: release.clone(),
+        })
+        .with_max_concurrent_requests(2);
+        let handle = tokio::spawn(server.run(ChannelTransport { incoming, outgoing }));
+
+        for (id, method) in [(1, "slow"), (2, "slow"), (3, "ping")] {
+            client_tx
+                .send(JsonRpcMessage::Request(JsonRpcRequest {
+                    jsonrpc: "2.0".to_string(),
+                    id: Some(RequestId::Number(id.into())),
+                    method: method.to_string(),
+                    params: None,
+                }))
+                .unwrap();
+        }
+
+        // Both slots are taken by the slow requests, so the ping has to wait
+        let waiting =
+            tokio::time::timeout(std::time::Duration::from_millis(50), client_rx.recv()).await;
+        assert!(waiting.is_err(), "Expected no response, got {:?}", waiting);
+
+        // Once a slow request completes the ping gets its slot
+        release.notify_one();
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            match client_rx.recv().await {
+                Some(JsonRpcMessage::Response(response)) => ids.push(response.id),
+                other => panic!("Expected Response, got {:?}", other),
+            }
+        }
+        assert!(ids.contains(&Some(RequestId::Number(3.into()))));
+
+        release.notify_one();
+        assert!(matches!(
+            client_rx.recv().await,
+            Some(JsonRpcMessage::Response(_))
+        ));
+        drop(client_tx);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batch_dispatch() {
+        let (client, server) = duplex(4096);
+        let (server_read, server_write) = tokio::io::split(server);
+        let (client_read, mut client_write) = tokio::io::split(client);
+
+        let server = Server::new(GatedService {
+            release: Arc::new(Notify::new()),
+        });
+        let peer = server.peer();
+        let handle = tokio::spawn(server.run(ByteTransport::new(server_read, server_write)));
+        let mut lines = BufReader::new(client_read).lines();
+        async fn next_batch<R: tokio::io::AsyncBufRead + Unpin>(
+            lines: &mut tokio::io::Lines<R>,
+        ) -> Vec<JsonRpcMessage> {
+            let line = lines.next_line().await.unwrap().unwrap();
+            match serde_json::from_str::<JsonRpcMessage>(&line).unwrap() {
+                JsonRpcMessage::Batch(messages) => messages,
+                other => panic!("Expected Batch, got {:?}", other),
+            }
+        }
+
+        // Notifications get no entry in the reply
+        client_write
+            .write_all(
+                b"[{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"},\
+                  {\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"},\
+                  {\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"tools/list\"}]\n",
+            )
+            .await
+            .unwrap();
+        let mut ids: Vec<_> = next_batch(&mut lines)
+            .await
+            .into_iter()
+            .map(|message| match message {
+                JsonRpcMessage::Response(response) => response.id,
+                other => panic!("Expected Response, got {:?}", other),
+            })
+            .collect();
+        ids.sort_by_key(|id| id.as_ref().map(ToString::to_string));
+        assert_eq!(
+            ids,
+            vec![
+                Some(RequestId::Number(1.into())),
+                Some(RequestId::Number(2.into()))
+            ]
+        );
+
+        // An invalid element is answered with an error, the valid ones are still handled
+        client_write
+            .write_all(
+                b"[{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"ping\"},{\"jsonrpc\":\"2.0\",\"id\":4}]\n",
+            )
+            .await
+            .unwrap();
+        let reply = next_batch(&mut lines).await;
+        assert_eq!(reply.len(), 2);
+        assert!(reply.iter().any(|message| matches!(
+            message,
+            JsonRpcMessage::Response(r) if r.id == Some(RequestId::Number(3.into()))
+        )));
+        assert!(reply.iter().any(|message| matches!(
+            message,
+            JsonRpcMessage::Error(e)
+                if e.id == Some(RequestId::Number(4.into())) && e.error.code == INVALID_REQUEST
+        )));
+
+        // A batch of nothing but invalid elements gets one error for each
+        client_write.write_all(b"[1,2]\n").await.unwrap();
+        let reply = next_batch(&mut lines).await;
+        assert_eq!(reply.len(), 2);
+        assert!(reply.iter().all(|message| matches!(
+            message,
+            JsonRpcMessage::Error(e) if e.id.is_none() && e.error.code == INVALID_REQUEST
+        )));
+
+        // Once a version without batching is agreed on, batches are rejected as a whole
+        peer.set_protocol_version(mcp_core::ProtocolVersion::V2024_11_05);
+        client_write
+            .write_all(b"[{\"jsonrpc\":\"2.0\",\"id\":5,\"method\":\"ping\"}]\n")
+            .await
+            .unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        assert!(matches!(
+            serde_json::from_str(&line).unwrap(),
+            JsonRpcMessage::Error(e) if e.id.is_none() && e.error.code == INVALID_REQUEST
+        ));
+
+        drop(client_write);
+        drop(lines);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_request_to_client() {
+        let (client_tx, incoming) = mpsc::unbounded_channel();
+        let (outgoing, mut client_rx) = mpsc::unbounded_channel();
+
+        let server = Server::new(GatedService {
+            release: Arc::new(Notify::new()),
+        })
+        .with_max_concurrent_requests(1);
+        let handle = tokio::spawn(server.run(ChannelTransport { incoming, outgoing }));
+
+        client_tx
+            .send(JsonRpcMessage::Request(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(RequestId::Number(1.into())),
+                method: "ask".to_string(),
+                params: None,
+            }))
+            .unwrap();
+
+        match client_rx.recv().await {
+            Some(JsonRpcMessage::Notification(notification)) => {
+                assert_eq!(notification.method, "notifications/asking");
+            }
+            other => panic!("Expected Notification, got {:?}", other),
+        }
+        let server_request = match client_rx.recv().await {
+            Some(JsonRpcMessage::Request(request)) => request,
+            other => panic!("Expected Request, got {:?}", other),
+        };
+        assert_eq!(server_request.method, "client/answer");
+
+        // The handler is still waiting, the answer must get through regardless of the limit
+        client_tx
+            .send(JsonRpcMessage::Response(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: server_request.id,
+                result: Some(serde_json::json!({ "answer": 42 })),
+                error: None,
+            }))
+            .unwrap();
+
+        match client_rx.recv().await {
+            Some(JsonRpcMessage::Response(response)) => {
+                assert_eq!(response.id, Some(RequestId::Number(1.into())));
+                assert_eq!(response.result, Some(serde_json::json!({ "answer": 42 })));
+            }
+            other => panic!("Expected Response, got {:?}", other),
+        }
+
+        drop(client_tx);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_client_answers_read_while_queue_is_full() {
+        let (client_tx, incoming) = mpsc::unbounded_channel();
+        let (outgoing, mut client_rx) = mpsc::unbounded_channel();
+
+        let server = Server::new(GatedService {
+            release: Arc::new(Notify::new()),
+        })
+        .with_max_concurrent_requests(1);
+        let handle = tokio::spawn(server.run(ChannelTransport { incoming, outgoing }));
+        let request = |id: u64, method: &str| {
+            JsonRpcMessage::Request(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(RequestId::from(id)),
+                method: method.to_string(),
+                params: None,
+            })
+        };
+
+        client_tx.send(request(1, "ask")).unwrap();
+        assert!(matches!(
+            client_rx.recv().await,
+            Some(JsonRpcMessage::Notification(_))
+        ));
+        let server_request = match client_rx.recv().await {
+            Some(JsonRpcMessage::Request(request)) => request,
+            other => panic!("Expected Request, got {:?}", other),
+        };
+
+        // The only slot waits on the client, the ping fills the queue and the next
+        // request doesn't fit
+        client_tx.send(request(2, "ping")).unwrap();
+        client_tx.send(request(3, "ping")).unwrap();
+        match client_rx.recv().await {
+            Some(JsonRpcMessage::Error(error)) => {
+                assert_eq!(error.id, Some(RequestId::from(3u64)));
+                assert_eq!(error.error.code, SERVER_BUSY);
+            }
+            other => panic!("Expected Error, got {:?}", other),
+        }
+
+        // The answer is still read, letting the handler and then the ping complete
+        client_tx
+            .send(JsonRpcMessage::Response(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: server_request.id,
+                result: Some(serde_json::json!({})),
+                error: None,
+            }))
+            .unwrap();
+        for id in [1u64, 2] {
+            match client_rx.recv().await {
+                Some(JsonRpcMessage::Response(response)) => {
+                    assert_eq!(response.id, Some(RequestId::from(id)));
+                }
+                other => panic!("Expected Response, got {:?}", other),
+            }
+        }
+
+        drop(client_tx);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_progress_notifications() {
+        let (client_tx, incoming) = mpsc::unbounded_channel();
+        let (outgoing, mut client_rx) = mpsc::unbounded_channel();
+
+        let server = Server::new(GatedService {
+            release: Arc::new(Notify::new()),
+        });
+        let handle = tokio::spawn(server.run(ChannelTransport { incoming, outgoing }));
+
+        client_tx
+            .send(JsonRpcMessage::Request(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(RequestId::Number(1.into())),
+                method: "index".to_string(),
+                params: Some(serde_json::json!({ "_meta": { "progressToken": "p1" } })),
+            }))
+            .unwrap();
+
+        for expected in [1.0, 2.0] {
+            match client_rx.recv().await {
+                Some(JsonRpcMessage::Notification(notification)) => {
+                    assert_eq!(notification.method, "notifications/progress");
+                    let params = notification.params.unwrap();
+                    assert_eq!(params["progressToken"], "p1");
+                    assert_eq!(params["progress"], expected);
+                    assert_eq!(params["total"], 2.0);
+                }
+                other => panic!("Expected Notification, got {:?}", other),
+            }
+        }
+        match client_rx.recv().await {
+            Some(JsonRpcMessage::Response(response)) => {
+                assert_eq!(
+                    response.result,
+                    Some(serde_json::json!({ "reported": true }))
+                );
+            }
+            other => panic!("Expected Response, got {:?}", other),
+        }
+
+        // Without a token the tool runs the same but nothing is reported
+        client_tx
+            .send(JsonRpcMessage::Request(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(RequestId::Number(2.into())),
+                method: "index".to_string(),
+                params: None,
+            }))
+            .unwrap();
+        match client_rx.recv().await {
+            Some(JsonRpcMessage::Response(response)) => {
+                assert_eq!(
+                    response.result,
+                    Some(serde_json::json!({ "reported": false }))
+                );
+            }
+            other => panic!("Expected Response, got {:?}", other),
+        }
+
+        drop(client_tx);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_gets_no_response() {
+        let (client_tx, incoming) = mpsc::unbounded_channel();
+        let (outgoing, mut client_rx) = mpsc::unbounded_channel();
+
+        let release = Arc::new(Notify::new());
+        let server = Server::new(GatedService {
+            release: release.clone(),
+        });
+        let handle = tokio::spawn(server.run(ChannelTransport { incoming, outgoing }));
+
+        let request = |id: u64, method: &str| {
+            JsonRpcMessage::Request(JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(RequestId::Number(id.into())),
+                method: method.to_string(),
+                params: None,
+            })
+        };
+        client_tx.send(request(1, "slow")).unwrap();
+        client_tx
+            .send(JsonRpcMessage::Notification(JsonRpcNotification {
+                jsonrpc: "2.0".to_string(),
+                method: "notifications/cancelled".to_string(),
+                params: Some(serde_json::json!({ "requestId": 1, "reason": "timeout" })),
+            }))
+            .unwrap();
+        client_tx.send(request(2, "ping")).unwrap();
+
+        match client_rx.recv().await {
+            Some(JsonRpcMessage::Response(response)) => {
+                assert_eq!(response.id, Some(RequestId::Number(2.into())));
+            }
+            other => panic!("Expected Response, got {:?}", other),
+        }
+
+        // Releasing the slow request has no effect, its handler is gone
+        release.notify_one();
+        drop(client_tx);
+        handle.await.unwrap().unwrap();
+        assert!(client_rx.recv().await.is_none());
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use mcp_core::protocol::ListChanged;
+
+use crate::Peer;
+
+/// Tells every connected client when the tools, prompts or resources a `Router` offers
+/// change.
+///
+/// A router that advertises `list_changed` in its capabilities keeps one of these and
+/// hands it out from [`Router::list_changed_notifier`](crate::Router::list_changed_notifier).
+/// Clients are registered as they initialize, after which the router can call
+/// [`ListChangedNotifier::notify`] whenever its catalog changes. Clones share the same
+/// clients, so the router can be cloned per connection.
+#[derive(Clone, Default)]
+pub struct ListChangedNotifier {
+    peers: Arc<Mutex<Vec<Peer>>>,
+    // Counts the changes to the tools, so that what was derived from them can be rebuilt
+    tools_version: Arc<AtomicU64>,
+}
+
+impl ListChangedNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start notifying the client behind `peer`
+    pub fn register(&self, peer: &Peer) {
+        let mut peers = self.peers();
+        peers.retain(|known| !known.is_closed());
+        if !peers.iter().any(|known| known.same_connection(peer)) {
+            peers.push(peer.clone());
+        }
+    }
+
+    /// Tell every connected client that `list` changed, returning how many were told
+    pub fn notify(&self, list: ListChanged) -> usize {
+        if list == ListChanged::Tools {
+            self.tools_version.fetch_add(1, Ordering::SeqCst);
+        }
+        let mut peers = self.peers();
+        peers.retain(|peer| !peer.is_closed());
+        peers
+            .iter()
+            .filter(|peer| peer.notify_list_changed(list).is_ok())
+            .count()
+    }
+
+    /// How many times the tools were announced as changed
+    pub(crate) fn tools_version(&self) -> u64 {
+        self.tools_version.load(Ordering::SeqCst)
+    }
+
+    fn peers(&self) -> std::sync::MutexGuard<'_, Vec<Peer>> {
+        self.peers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for ListChangedNotifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ListChangedNotifier")
+            .field("peers", &self.peers().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::protocol::JsonRpcMessage;
+
+    #[test]
+    fn test_notifies_registered_clients() {
+        let notifier = ListChangedNotifier::new();
+        let (peer, mut outgoing) = Peer::new();
+        let (gone, gone_rx) = Peer::new();
+
+        notifier.register(&peer);
+        notifier.register(&peer);
+        notifier.register(&gone);
+        drop(gone_rx);
+
+        assert_eq!(notifier.notify(ListChanged::Tools), 1);
+        match outgoing.try_recv() {
+            Ok(JsonRpcMessage::Notification(notification)) => {
+                assert_eq!(notification.method, "notifications/tools/list_changed");
+            }
+            other => panic!("Expected Notification, got {:?}", other),
+        }
+        assert!(outgoing.try_recv().is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use mcp_core::protocol::LoggingLevel;
+use serde_json::{Map, Value};
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::{layer::Context, Layer};
+
+use crate::Peer;
+
+/// A `tracing_subscriber` layer that forwards server log events to the connected client as
+/// `notifications/message`.
+///
+/// Only events at or above the level the client selected with `logging/setLevel` are sent,
+/// and nothing is sent before it selects one. Events emitted by this crate itself are never
+/// forwarded, as sending them could produce more of them. The router should advertise the
+/// logging capability with
+/// [`CapabilitiesBuilder::with_logging`](crate::router::CapabilitiesBuilder::with_logging).
+///
+/// ```no_run
+/// # use mcp_server::{logging::ClientLoggingLayer, Peer};
+/// # use tracing_subscriber::prelude::*;
+/// # fn run(peer: Peer) {
+/// // The peer of the server, from `Server::peer`
+/// tracing_subscriber::registry()
+///     .with(ClientLoggingLayer::new(peer))
+///     .init();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientLoggingLayer {
+    peer: Peer,
+}
+
+impl ClientLoggingLayer {
+    pub fn new(peer: Peer) -> Self {
+        Self { peer }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ClientLoggingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            return;
+        }
+
+        let level = to_logging_level(metadata.level());
+        if self.peer.log_level().is_none_or(|min| level < min) {
+            return;
+        }
+
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+        let data = Value::Object(fields.0);
+
+        // A closed connection is the only failure, and there is nobody left to tell
+        let _ = self
+            .peer
+            .send_log(level, Some(metadata.target().to_string()), data);
+    }
+}
+
+/// Map a `tracing` level onto the closest MCP logging level
+pub fn to_logging_level(level: &Level) -> LoggingLevel {
+    match *level {
+        Level::ERROR => LoggingLevel::Error,
+        Level::WARN => LoggingLevel::Warning,
+        Level::INFO => LoggingLevel::Info,
+        Level::DEBUG | Level::TRACE => LoggingLevel::Debug,
+    }
+}
+
+/// Collects the fields of an event, including its message, into a JSON object
+#[derive(Default)]
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::protocol::JsonRpcMessage;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn test_forwards_events_at_client_level() {
+        let (peer, mut outgoing) = Peer::new();
+        let subscriber = tracing_subscriber::registry().with(ClientLoggingLayer::new(peer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!(target: "indexer", "before the client picked a level");
+            peer.set_log_level(LoggingLevel::Warning);
+            tracing::info!(target: "indexer", "too verbose");
+            tracing::warn!(target: "indexer", files = 3, "slow disk");
+        });
+
+        let Ok(JsonRpcMessage::Notification(notification)) = outgoing.try_recv() else {
+            panic!("Expected a log notification");
+        };
+        assert_eq!(notification.method, "notifications/message");
+        assert_eq!(
+            notification.params.unwrap(),
+            serde_json::json!({
+                "level": "warning",
+                "logger": "indexer",
+                "data": {"message": "slow disk", "files": 3}
+            })
+        );
+        assert!(outgoing.try_recv().is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use mcp_core::tool::Tool;
+use serde_json::Value;
+
+/// An output schema compiled for validation, or why it failed to compile
+type CompiledSchema = Arc<Result<jsonschema::Validator, String>>;
+
+/// The compiled output schemas of a router's tools, keyed by tool name.
+///
+/// Checking structured tool output would otherwise list the tools and compile a schema
+/// on every call. The schemas are compiled on the first call, and again once the
+/// router's [`ListChangedNotifier`](crate::ListChangedNotifier) announces that its tools
+/// changed or a tool they were not compiled with is called. Clones share the same cache.
+#[derive(Clone, Default)]
+pub struct OutputSchemas {
+    cache: Arc<Mutex<Option<Cache>>>,
+}
+
+struct Cache {
+    /// The tools version of the notifier the schemas were compiled at, if there is one
+    tools_version: Option<u64>,
+    /// Every listed tool, with its schema if it declares one
+    schemas: HashMap<String, Option<CompiledSchema>>,
+}
+
+impl OutputSchemas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The compiled schema of `tool` if the cache is up to date with `tools_version`,
+    /// which holds None when the tool declares no output schema. A tool missing from the
+    /// cache makes it stale too, as without a notifier that is the only sign of new tools.
+    pub(crate) fn get(&self, tools_version: Option<u64>, tool: &str) -> Option<Option<Schema>> {
+        let cache = self.cache();
+        let cache = cache
+            .as_ref()
+            .filter(|cache| cache.tools_version == tools_version)?;
+        let schema = cache.schemas.get(tool)?;
+        Some(schema.clone().map(Schema))
+    }
+
+    /// Compile the output schemas of `tools` in place of the cached ones, returning the
+    /// one of `tool`
+    pub(crate) fn rebuild(
+        &self,
+        tools_version: Option<u64>,
+        tools: Vec<Tool>,
+        tool: &str,
+    ) -> Option<Schema> {
+        let schemas: HashMap<_, _> = tools
+            .into_iter()
+            .map(|tool| {
+                let compiled = tool.output_schema.map(|schema| {
+                    Arc::new(jsonschema::validator_for(&schema).map_err(|e| e.to_string()))
+                });
+                (tool.name, compiled)
+            })
+            .collect();
+        let schema = schemas.get(tool).cloned().flatten().map(Schema);
+        *self.cache() = Some(Cache {
+            tools_version,
+            schemas,
+        });
+        schema
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, Option<Cache>> {
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for OutputSchemas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let compiled = self.cache().as_ref().map_or(0, |cache| {
+            cache
+                .schemas
+                .values()
+                .filter(|schema| schema.is_some())
+                .count()
+        });
+        f.debug_struct("OutputSchemas")
+            .field("compiled", &compiled)
+            .finish()
+    }
+}
+
+/// The compiled output schema of one tool
+pub(crate) struct Schema(CompiledSchema);
+
+impl Schema {
+    /// Validate `value` against the schema, describing the first violation
+    pub(crate) fn validate(&self, value: &Value) -> Result<(), String> {
+        match self.0.as_ref() {
+            Ok(validator) => validator.validate(value).map_err(|e| e.to_string()),
+            Err(e) => Err(format!("invalid output schema: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rebuilt_when_tools_change() {
+        let schemas = OutputSchemas::new();
+        let tool = |schema| Tool::new("count", "Count lines", json!({})).with_output_schema(schema);
+        let lines = json!({ "type": "object", "required": ["lines"] });
+
+        assert!(schemas.get(None, "count").is_none());
+        let schema = schemas
+            .rebuild(Some(0), vec![tool(lines)], "count")
+            .unwrap();
+        assert!(schema.validate(&json!({ "lines": 1 })).is_ok());
+        assert!(schemas.get(Some(0), "count").unwrap().is_some());
+        assert!(schemas.get(Some(0), "other").is_none());
+
+        // Once the tools changed the cached schemas are stale
+        assert!(schemas.get(Some(1), "count").is_none());
+        let words = json!({ "type": "object", "required": ["words"] });
+        let schema = schemas
+            .rebuild(Some(1), vec![tool(words)], "count")
+            .unwrap();
+        assert!(schema.validate(&json!({ "lines": 1 })).is_err());
+    }
+
+    #[test]
+    fn test_rebuilt_for_new_tool_without_notifier() {
+        let schemas = OutputSchemas::new();
+        let count = Tool::new("count", "Count lines", json!({}));
+        let lines = json!({ "type": "object", "required": ["lines"] });
+        let words = Tool::new("words", "Count words", json!({})).with_output_schema(lines);
+
+        assert!(schemas
+            .rebuild(None, vec![count.clone()], "count")
+            .is_none());
+        assert!(schemas.get(None, "count").unwrap().is_none());
+
+        // A tool the cache has never seen was added since, so the cache must be rebuilt
+        assert!(schemas.get(None, "words").is_none());
+        let schema = schemas.rebuild(None, vec![count, words], "words").unwrap();
+        assert!(schema.validate(&json!({ "lines": 1 })).is_ok());
+        assert!(schemas.get(None, "words").unwrap().is_some());
+    }
+}
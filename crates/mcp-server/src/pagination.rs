@@ -0,0 +1,114 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash, Hasher},
+    sync::OnceLock,
+};
+
+use mcp_core::protocol::PaginatedRequest;
+use serde_json::Value;
+
+use crate::RouterError;
+
+/// The number of items a list handler returns per page unless the router picks another
+/// size with [`Router::page_size`](crate::Router::page_size)
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// One page of a list, with the cursor of the next page if there is one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Cut the page at `cursor` out of the whole list `items`. `list` names the list, e.g.
+    /// "tools", and must be the same when the cursor is decoded.
+    pub fn from_list(
+        items: Vec<T>,
+        list: &str,
+        cursor: Option<&str>,
+        page_size: usize,
+    ) -> Result<Self, RouterError> {
+        let offset = match cursor {
+            Some(cursor) => decode_cursor(list, cursor)?,
+            None => 0,
+        };
+        if offset > items.len() {
+            return Err(RouterError::InvalidParams("Invalid cursor".into()));
+        }
+
+        let end = offset.saturating_add(page_size.max(1)).min(items.len());
+        let next_cursor = (end < items.len()).then(|| encode_cursor(list, end));
+        let items = items.into_iter().skip(offset).take(end - offset).collect();
+        Ok(Self { items, next_cursor })
+    }
+}
+
+/// The cursor of a list request, if the client is asking for a page after the first
+pub fn request_cursor(params: Option<&Value>) -> Result<Option<String>, RouterError> {
+    let Some(params) = params else {
+        return Ok(None);
+    };
+    let request: PaginatedRequest = serde_json::from_value(params.clone())
+        .map_err(|e| RouterError::InvalidParams(format!("Invalid cursor: {}", e)))?;
+    Ok(request.cursor)
+}
+
+/// Encode the cursor of the page of `list` starting at `offset`.
+///
+/// Cursors are opaque to clients: they hold the offset along with a keyed hash of it,
+/// so a client can't forge a cursor or reuse one for another list. The key is picked at
+/// random when the server starts, so cursors don't survive a restart, which clients see
+/// as an invalid params error.
+pub fn encode_cursor(list: &str, offset: usize) -> String {
+    format!("{:x}.{:016x}", offset, signature(list, offset))
+}
+
+/// Decode a cursor made by [`encode_cursor`] for the same `list`, returning its offset
+pub fn decode_cursor(list: &str, cursor: &str) -> Result<usize, RouterError> {
+    let invalid = || RouterError::InvalidParams("Invalid cursor".into());
+    let (offset, signature_hex) = cursor.split_once('.').ok_or_else(invalid)?;
+    let offset = usize::from_str_radix(offset, 16).map_err(|_| invalid())?;
+    let expected = u64::from_str_radix(signature_hex, 16).map_err(|_| invalid())?;
+    if signature_hex.len() != 16 || signature(list, offset) != expected {
+        return Err(invalid());
+    }
+    Ok(offset)
+}
+
+fn signature(list: &str, offset: usize) -> u64 {
+    // SipHash with random keys, which the standard library uses to resist hash flooding,
+    // makes a keyed hash that clients can't compute without the key
+    static KEY: OnceLock<RandomState> = OnceLock::new();
+    let mut hasher = KEY.get_or_init(RandomState::new).build_hasher();
+    list.hash(&mut hasher);
+    offset.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages() {
+        let items: Vec<u32> = (0..5).collect();
+
+        let first = Page::from_list(items.clone(), "tools", None, 2).unwrap();
+        assert_eq!(first.items, vec![0, 1]);
+        let cursor = first.next_cursor.unwrap();
+
+        let second = Page::from_list(items.clone(), "tools", Some(&cursor), 2).unwrap();
+        assert_eq!(second.items, vec![2, 3]);
+        let last =
+            Page::from_list(items.clone(), "tools", second.next_cursor.as_deref(), 2).unwrap();
+        assert_eq!(last.items, vec![4]);
+        assert_eq!(last.next_cursor, None);
+
+        // Cursors can't be reused for another list or altered
+        assert!(Page::from_list(items.clone(), "prompts", Some(&cursor), 2).is_err());
+        let forged = cursor.replacen('2', "3", 1);
+        assert!(Page::from_list(items.clone(), "tools", Some(&forged), 2).is_err());
+        assert!(Page::from_list(items, "tools", Some("garbage"), 2).is_err());
+    }
+}
@@ -0,0 +1,769 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use mcp_core::elicitation::{ElicitAction, ElicitRequest, ElicitResult};
+use mcp_core::protocol::{
+    CancelledNotification, ClientCapabilities, JsonRpcError, JsonRpcMessage, JsonRpcNotification,
+    JsonRpcRequest, JsonRpcResponse, ListChanged, ListRootsRequest, ListRootsResult, LoggingLevel,
+    LoggingMessageNotification, ProgressNotification, ProgressToken, RequestId, Root,
+};
+use mcp_core::sampling::{CreateMessageRequest, CreateMessageResult};
+use mcp_core::{Meta, ProtocolVersion};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio_util::sync::CancellationToken;
+
+use crate::PeerError;
+
+type PendingResponses = HashMap<RequestId, oneshot::Sender<Result<Value, PeerError>>>;
+
+/// A handle for talking back to the client connected to a [`Server`](crate::Server).
+///
+/// The server only answers requests on its own, a `Peer` lets it send notifications and
+/// issue requests of its own to the client, e.g. for sampling or roots. Responses to those
+/// requests are matched by id as the server reads them from the transport. Handles are
+/// cheap to clone and can be moved into background tasks.
+#[derive(Clone)]
+pub struct Peer {
+    inner: Arc<PeerInner>,
+}
+
+struct PeerInner {
+    sender: mpsc::UnboundedSender<JsonRpcMessage>,
+    pending: Mutex<PendingResponses>,
+    next_id: AtomicU64,
+    log_level: Mutex<Option<LoggingLevel>>,
+    client_capabilities: Mutex<Option<ClientCapabilities>>,
+    protocol_version: Mutex<Option<ProtocolVersion>>,
+    roots_changed: watch::Sender<()>,
+}
+
+impl std::fmt::Debug for Peer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Peer")
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+impl Peer {
+    /// Create a peer along with the receiving end of its outgoing messages, which the
+    /// server drains into the transport
+    pub(crate) fn new() -> (Self, mpsc::UnboundedReceiver<JsonRpcMessage>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let peer = Self {
+            inner: Arc::new(PeerInner {
+                sender,
+                pending: Mutex::new(HashMap::new()),
+                next_id: AtomicU64::new(1),
+                log_level: Mutex::new(None),
+                client_capabilities: Mutex::new(None),
+                protocol_version: Mutex::new(None),
+                roots_changed: watch::Sender::new(()),
+            }),
+        };
+        (peer, receiver)
+    }
+
+    /// Returns true once the connection this peer belongs to has ended
+    pub fn is_closed(&self) -> bool {
+        self.inner.sender.is_closed()
+    }
+
+    /// Returns true if both handles talk to the same client connection
+    pub fn same_connection(&self, other: &Peer) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Send a notification to the client
+    pub fn send_notification<P: Serialize>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<(), PeerError> {
+        let notification = JsonRpcMessage::Notification(JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: Some(serde_json::to_value(params)?),
+        });
+        self.inner
+            .sender
+            .send(notification)
+            .map_err(|_| PeerError::Closed)
+    }
+
+    /// Tell the client that a list it may have fetched, such as the tools, changed
+    pub fn notify_list_changed(&self, list: ListChanged) -> Result<(), PeerError> {
+        self.send_notification(list.method(), serde_json::json!({}))
+    }
+
+    /// Send a request to the client and wait for its result
+    pub fn send_request<P, R>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> impl Future<Output = Result<R, PeerError>> + Send + 'static
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let sent = self.start_request(method, params);
+        // The request is out as soon as this returns, so it must be cancelled even if the
+        // future is dropped before it is first polled
+        let cancel = sent
+            .as_ref()
+            .ok()
+            .map(|(id, _)| CancelOnDrop::new(self.clone(), id.clone()));
+        async move {
+            let _cancel = cancel;
+            let (_, response) = sent?;
+            let value = response.await.map_err(|_| PeerError::Closed)??;
+            Ok(serde_json::from_value(value)?)
+        }
+    }
+
+    fn start_request<P: Serialize>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<(RequestId, oneshot::Receiver<Result<Value, PeerError>>), PeerError> {
+        let params = serde_json::to_value(params)?;
+        let id = RequestId::Number(self.inner.next_id.fetch_add(1, Ordering::SeqCst).into());
+        let (tx, rx) = oneshot::channel();
+        self.pending().insert(id.clone(), tx);
+
+        let request = JsonRpcMessage::Request(JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id.clone()),
+            method: method.to_string(),
+            params: Some(params),
+        });
+        if self.inner.sender.send(request).is_err() {
+            self.pending().remove(&id);
+            return Err(PeerError::Closed);
+        }
+
+        Ok((id, rx))
+    }
+
+    /// Ask the client to sample a message from its LLM, e.g. to let a tool summarize
+    /// text. The client decides which model to use and may ask its user to approve the
+    /// request first, so this can take a while.
+    pub fn create_message(
+        &self,
+        request: CreateMessageRequest,
+    ) -> impl Future<Output = Result<CreateMessageResult, PeerError>> + Send + 'static {
+        let supported = self
+            .client_capabilities()
+            .is_some_and(|capabilities| capabilities.sampling.is_some());
+        let response = supported.then(|| self.send_request(CreateMessageRequest::METHOD, request));
+        async move {
+            match response {
+                Some(response) => response.await,
+                None => Err(PeerError::Unsupported("sampling")),
+            }
+        }
+    }
+
+    /// Ask the user for input through the client, e.g. to confirm a deployment or fill in
+    /// a missing field in the middle of a tool call. Content the user accepted is checked
+    /// against the requested schema, so handlers can rely on its shape.
+    ///
+    /// ```no_run
+    /// # use mcp_core::elicitation::{ElicitAction, ElicitRequest, ElicitationSchema, PrimitiveSchema};
+    /// # use mcp_server::RequestContext;
+    /// # async fn deploy() -> Result<(), mcp_server::PeerError> {
+    /// let context = RequestContext::current().expect("called from a tool");
+    /// let form = ElicitationSchema::new().required("confirm", PrimitiveSchema::boolean("Deploy now?"));
+    /// let result = context
+    ///     .peer()
+    ///     .elicit(ElicitRequest::new("Deploy v2 to production?", form))
+    ///     .await?;
+    /// if result.action == ElicitAction::Accept {
+    ///     // ... deploy ...
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn elicit(
+        &self,
+        request: ElicitRequest,
+    ) -> impl Future<Output = Result<ElicitResult, PeerError>> + Send + 'static {
+        let supported = self
+            .client_capabilities()
+            .is_some_and(|capabilities| capabilities.elicitation.is_some())
+            && self
+                .protocol_version()
+                .is_none_or(ProtocolVersion::supports_elicitation);
+        let schema = request.requested_schema.clone();
+        let response = supported.then(|| self.send_request(ElicitRequest::METHOD, request));
+        async move {
+            let Some(response) = response else {
+                return Err(PeerError::Unsupported("elicitation"));
+            };
+            let result: ElicitResult = response.await?;
+            if result.action == ElicitAction::Accept {
+                let content = result.content.as_ref().ok_or_else(|| {
+                    PeerError::InvalidResponse("Accepted elicitation without content".to_string())
+                })?;
+                schema
+                    .validate(content)
+                    .map_err(PeerError::InvalidResponse)?;
+            }
+            Ok(result)
+        }
+    }
+
+    /// Ask the client for its roots, the directories and files the server may operate
+    /// on. Filesystem servers should call this again whenever [`Peer::roots_changed`]
+    /// fires.
+    pub fn list_roots(
+        &self,
+    ) -> impl Future<Output = Result<Vec<Root>, PeerError>> + Send + 'static {
+        let supported = self
+            .client_capabilities()
+            .is_some_and(|capabilities| capabilities.roots.is_some());
+        let response = supported.then(|| {
+            self.send_request::<_, ListRootsResult>(
+                ListRootsRequest::METHOD,
+                ListRootsRequest { meta: None },
+            )
+        });
+        async move {
+            match response {
+                Some(response) => Ok(response.await?.roots),
+                None => Err(PeerError::Unsupported("roots")),
+            }
+        }
+    }
+
+    /// A receiver that is marked changed whenever the client announces that its roots
+    /// changed. Subscribe before listing the roots so that no change goes unnoticed:
+    ///
+    /// ```no_run
+    /// # use mcp_server::Peer;
+    /// # async fn scope(peer: Peer) -> Result<(), mcp_server::PeerError> {
+    /// let mut changes = peer.roots_changed();
+    /// loop {
+    ///     let roots = peer.list_roots().await?;
+    ///     // ... limit the server to `roots` ...
+    ///     if changes.changed().await.is_err() {
+    ///         return Ok(());
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn roots_changed(&self) -> watch::Receiver<()> {
+        self.inner.roots_changed.subscribe()
+    }
+
+    /// Record that the client's roots changed, waking whoever watches them
+    pub(crate) fn notify_roots_changed(&self) {
+        self.inner.roots_changed.send_replace(());
+    }
+
+    /// The capabilities the client announced when it initialized the connection, None
+    /// until it has
+    pub fn client_capabilities(&self) -> Option<ClientCapabilities> {
+        self.inner
+            .client_capabilities
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub(crate) fn set_client_capabilities(&self, capabilities: ClientCapabilities) {
+        *self
+            .inner
+            .client_capabilities
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(capabilities);
+    }
+
+    /// The protocol version agreed on with the client, None until it has initialized the
+    /// connection
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        *self
+            .inner
+            .protocol_version
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn set_protocol_version(&self, version: ProtocolVersion) {
+        *self
+            .inner
+            .protocol_version
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(version);
+    }
+
+    /// The minimum level of the log messages the client asked for, None until it sends
+    /// `logging/setLevel`
+    pub fn log_level(&self) -> Option<LoggingLevel> {
+        *self
+            .inner
+            .log_level
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Set the minimum level of the log messages sent to the client
+    pub fn set_log_level(&self, level: LoggingLevel) {
+        *self
+            .inner
+            .log_level
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(level);
+    }
+
+    /// Send a log message to the client, if it asked for messages at this level. Returns
+    /// whether the message was sent.
+    pub fn send_log(
+        &self,
+        level: LoggingLevel,
+        logger: Option<String>,
+        data: Value,
+    ) -> Result<bool, PeerError> {
+        if self.log_level().is_none_or(|min| level < min) {
+            return Ok(false);
+        }
+        let message = LoggingMessageNotification {
+            level,
+            logger,
+            data,
+            meta: None,
+        };
+        self.send_notification(LoggingMessageNotification::METHOD, message)?;
+        Ok(true)
+    }
+
+    /// Route a response or error from the client to the request waiting for it. Returns
+    /// false if no request with that id is pending.
+    pub(crate) fn handle_response(&self, message: JsonRpcMessage) -> bool {
+        let (id, result) = match message {
+            JsonRpcMessage::Response(JsonRpcResponse {
+                id: Some(id),
+                result,
+                error,
+                ..
+            }) => {
+                let result = match error {
+                    Some(error) => Err(PeerError::RpcError {
+                        code: error.code,
+                        message: error.message,
+                    }),
+                    None => Ok(result.unwrap_or(Value::Null)),
+                };
+                (id, result)
+            }
+            JsonRpcMessage::Error(JsonRpcError {
+                id: Some(id),
+                error,
+                ..
+            }) => (
+                id,
+                Err(PeerError::RpcError {
+                    code: error.code,
+                    message: error.message,
+                }),
+            ),
+            _ => return false,
+        };
+
+        match self.pending().remove(&id) {
+            Some(tx) => {
+                let _ = tx.send(result);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fail every request still waiting for the client, used once the connection ends
+    pub(crate) fn close(&self) {
+        self.pending().clear();
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, PendingResponses> {
+        // The map stays consistent even if a holder panicked, so ignore poisoning
+        self.inner
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Cancels a request sent to the client once the future waiting for its result is
+/// dropped without it, forgetting the pending response and telling the client to stop
+struct CancelOnDrop {
+    peer: Peer,
+    id: RequestId,
+}
+
+impl CancelOnDrop {
+    fn new(peer: Peer, id: RequestId) -> Self {
+        Self { peer, id }
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        // Nothing is pending once the response arrived or the connection ended
+        if self.peer.pending().remove(&self.id).is_none() {
+            return;
+        }
+        let cancelled = CancelledNotification {
+            request_id: self.id.clone(),
+            reason: Some("Request was abandoned by the server".to_string()),
+            meta: None,
+        };
+        let _ = self
+            .peer
+            .send_notification(CancelledNotification::METHOD, cancelled);
+    }
+}
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: RequestContext;
+}
+
+/// Information about the request currently being handled by the server.
+///
+/// The server makes the context available for the whole time it processes a request, so
+/// `Router` implementations can call [`RequestContext::current`] from their handlers, or
+/// from the futures those handlers return, to reach the client. The context is not
+/// inherited by spawned tasks, clone it and move it into them instead.
+#[derive(Clone, Debug)]
+pub struct RequestContext {
+    peer: Peer,
+    request_id: Option<RequestId>,
+    progress_token: Option<ProgressToken>,
+    meta: Meta,
+    cancellation: CancellationToken,
+}
+
+impl RequestContext {
+    pub(crate) fn new(
+        peer: Peer,
+        request: &JsonRpcRequest,
+        cancellation: CancellationToken,
+    ) -> Self {
+        Self {
+            peer,
+            request_id: request.id.clone(),
+            progress_token: request.progress_token(),
+            meta: request.meta().unwrap_or_default(),
+            cancellation,
+        }
+    }
+
+    /// The context of the request being handled, if called while the server processes one
+    pub fn current() -> Option<Self> {
+        REQUEST_CONTEXT.try_with(Clone::clone).ok()
+    }
+
+    /// The client that sent the request
+    pub fn peer(&self) -> &Peer {
+        &self.peer
+    }
+
+    /// The id of the request
+    pub fn request_id(&self) -> Option<&RequestId> {
+        self.request_id.as_ref()
+    }
+
+    /// The token the client attached to ask for progress updates, if any
+    pub fn progress_token(&self) -> Option<&ProgressToken> {
+        self.progress_token.as_ref()
+    }
+
+    /// The metadata the client attached to the request under `_meta`, such as trace
+    /// context or extension keys
+    pub fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    /// A token that is cancelled once the client cancels this request.
+    ///
+    /// The server stops polling a cancelled request on its own, so handlers only need it
+    /// for work they hand off to other tasks, which should stop once it fires.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Returns true if the client cancelled this request
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// A reporter for the progress of this request
+    pub fn progress(&self) -> ProgressReporter {
+        ProgressReporter {
+            peer: self.peer.clone(),
+            token: self.progress_token.clone(),
+        }
+    }
+
+    /// Run `f` with this context set as the current one
+    pub(crate) fn sync_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        REQUEST_CONTEXT.sync_scope(self.clone(), f)
+    }
+
+    /// Poll `future` with this context set as the current one
+    pub(crate) fn scope<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        REQUEST_CONTEXT.scope(self, future)
+    }
+}
+
+/// Reports the progress of a long-running request to the client.
+///
+/// Progress is only sent if the client asked for it by attaching a progress token to the
+/// request, otherwise reporting does nothing. This lets tools report progress
+/// unconditionally:
+///
+/// ```no_run
+/// # use mcp_server::peer::ProgressReporter;
+/// # async fn index(files: Vec<String>) {
+/// let progress = ProgressReporter::current();
+/// for (done, file) in files.iter().enumerate() {
+///     // ... index the file ...
+///     progress.report(done as f64 + 1.0, Some(files.len() as f64), Some(format!("Indexed {file}")));
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ProgressReporter {
+    peer: Peer,
+    token: Option<ProgressToken>,
+}
+
+impl ProgressReporter {
+    /// The reporter for the request being handled. Outside of a request it does nothing.
+    pub fn current() -> Self {
+        match RequestContext::current() {
+            Some(context) => context.progress(),
+            None => Self {
+                peer: Peer::new().0,
+                token: None,
+            },
+        }
+    }
+
+    /// Returns true if the client asked for progress on this request
+    pub fn is_enabled(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// Send a progress notification. `progress` should increase with every call, `total`
+    /// is the amount of work if it is known. Failures to reach the client are ignored, as
+    /// progress is best effort and the request itself will fail to respond anyway.
+    pub fn report(&self, progress: f64, total: Option<f64>, message: Option<String>) {
+        let Some(token) = &self.token else {
+            return;
+        };
+        // Older clients don't expect a message with the progress
+        let message = message.filter(|_| {
+            self.peer
+                .protocol_version()
+                .is_none_or(ProtocolVersion::supports_progress_message)
+        });
+        let notification = ProgressNotification {
+            progress_token: token.clone(),
+            progress,
+            total,
+            message,
+            meta: None,
+        };
+        if let Err(e) = self
+            .peer
+            .send_notification(ProgressNotification::METHOD, notification)
+        {
+            tracing::debug!(error = %e, "Failed to send progress notification");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::{
+        elicitation::{ElicitationSchema, PrimitiveSchema},
+        protocol::{ElicitationCapability, RootsCapability, SamplingCapability},
+        sampling::SamplingMessage,
+        Content, Role,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_request_context_meta() {
+        let (peer, _outgoing) = Peer::new();
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(RequestId::Number(1.into())),
+            method: "tools/call".to_string(),
+            params: Some(json!({
+                "name": "index",
+                "_meta": {"progressToken": 5, "example.com/trace": "abc"}
+            })),
+        };
+
+        let context = RequestContext::new(peer, &request, CancellationToken::new());
+        assert_eq!(
+            context.progress_token(),
+            Some(&ProgressToken::Number(5.into()))
+        );
+        assert_eq!(context.meta().get("example.com/trace"), Some(&json!("abc")));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_request_is_cancelled() {
+        let (peer, mut outgoing) = Peer::new();
+        let response = peer.send_request::<_, Value>("client/answer", json!({}));
+
+        let Some(JsonRpcMessage::Request(sent)) = outgoing.recv().await else {
+            panic!("Expected a request");
+        };
+        drop(response);
+
+        let Some(JsonRpcMessage::Notification(notification)) = outgoing.recv().await else {
+            panic!("Expected a cancellation");
+        };
+        assert_eq!(notification.method, CancelledNotification::METHOD);
+        let cancelled: CancelledNotification =
+            serde_json::from_value(notification.params.unwrap()).unwrap();
+        assert_eq!(Some(cancelled.request_id), sent.id);
+        assert!(peer.pending().is_empty());
+
+        // A late answer is not routed anywhere
+        assert!(
+            !peer.handle_response(JsonRpcMessage::Response(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: sent.id,
+                result: Some(json!({})),
+                error: None,
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_message_requires_sampling_capability() {
+        let (peer, mut outgoing) = Peer::new();
+        let request = CreateMessageRequest::new(vec![SamplingMessage::user_text("Hi")], 10);
+
+        let result = peer.create_message(request.clone()).await;
+        assert!(matches!(result, Err(PeerError::Unsupported("sampling"))));
+        assert!(outgoing.try_recv().is_err());
+
+        peer.set_client_capabilities(ClientCapabilities {
+            sampling: Some(SamplingCapability {}),
+            ..Default::default()
+        });
+        let response = tokio::spawn(peer.create_message(request));
+
+        let Some(JsonRpcMessage::Request(sent)) = outgoing.recv().await else {
+            panic!("Expected a sampling request");
+        };
+        assert_eq!(sent.method, "sampling/createMessage");
+        assert!(
+            peer.handle_response(JsonRpcMessage::Response(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: sent.id,
+                result: Some(json!({
+                    "role": "assistant",
+                    "content": {"type": "text", "text": "Hello"},
+                    "model": "stub"
+                })),
+                error: None,
+            }))
+        );
+
+        let result = response.await.unwrap().unwrap();
+        assert_eq!(result.role, Role::Assistant);
+        assert_eq!(result.content, Content::text("Hello"));
+        assert_eq!(result.model, "stub");
+    }
+
+    #[tokio::test]
+    async fn test_elicit_validates_accepted_content() {
+        let (peer, mut outgoing) = Peer::new();
+        peer.set_client_capabilities(ClientCapabilities {
+            elicitation: Some(ElicitationCapability {}),
+            ..Default::default()
+        });
+        let form =
+            ElicitationSchema::new().required("replicas", PrimitiveSchema::integer("Replicas"));
+
+        for (content, valid) in [
+            (json!({"replicas": 3}), true),
+            (json!({"replicas": "many"}), false),
+        ] {
+            let result = tokio::spawn(peer.elicit(ElicitRequest::new("How many?", form.clone())));
+            let Some(JsonRpcMessage::Request(sent)) = outgoing.recv().await else {
+                panic!("Expected an elicitation request");
+            };
+            assert_eq!(sent.method, "elicitation/create");
+            peer.handle_response(JsonRpcMessage::Response(JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: sent.id,
+                result: Some(json!({"action": "accept", "content": content})),
+                error: None,
+            }));
+
+            match result.await.unwrap() {
+                Ok(result) => {
+                    assert!(valid);
+                    assert_eq!(result.action, ElicitAction::Accept);
+                }
+                Err(e) => {
+                    assert!(!valid);
+                    assert!(matches!(e, PeerError::InvalidResponse(_)));
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_roots() {
+        let (peer, mut outgoing) = Peer::new();
+        assert!(matches!(
+            peer.list_roots().await,
+            Err(PeerError::Unsupported("roots"))
+        ));
+
+        peer.set_client_capabilities(ClientCapabilities {
+            roots: Some(RootsCapability {
+                list_changed: Some(true),
+            }),
+            ..Default::default()
+        });
+        let changes = peer.roots_changed();
+        let roots = tokio::spawn(peer.list_roots());
+
+        let Some(JsonRpcMessage::Request(sent)) = outgoing.recv().await else {
+            panic!("Expected a roots request");
+        };
+        assert_eq!(sent.method, "roots/list");
+        peer.handle_response(JsonRpcMessage::Response(JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: sent.id,
+            result: Some(json!({"roots": [{"uri": "file:///project", "name": "Project"}]})),
+            error: None,
+        }));
+        assert_eq!(
+            roots.await.unwrap().unwrap(),
+            vec![Root::new("file:///project").with_name("Project")]
+        );
+
+        assert!(!changes.has_changed().unwrap());
+        peer.notify_roots_changed();
+        assert!(changes.has_changed().unwrap());
+    }
+}
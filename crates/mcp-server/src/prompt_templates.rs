@@ -0,0 +1,72 @@
+use mcp_core::{
+    prompt::{Prompt, PromptTemplate},
+    template::TemplateError,
+};
+
+/// The prompt templates a `Router` serves, compiled once as they are added.
+///
+/// A router keeps one of these and hands it out from
+/// [`Router::prompt_templates`](crate::Router::prompt_templates). Its templates are then
+/// listed along with `list_prompts` and rendered by the default `get_prompt_result`,
+/// without being compiled again. Clones share the compiled templates.
+#[derive(Clone, Debug, Default)]
+pub struct PromptTemplates {
+    templates: Vec<PromptTemplate>,
+}
+
+impl PromptTemplates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compile `template` and add it in place of any template with the same id. A template
+    /// that doesn't compile is not added.
+    pub fn add(&mut self, template: PromptTemplate) -> Result<(), TemplateError> {
+        template.compile()?;
+        self.templates.retain(|existing| existing.id != template.id);
+        self.templates.push(template);
+        Ok(())
+    }
+
+    /// Same as [`PromptTemplates::add`], for building the templates up front
+    pub fn with_template(mut self, template: PromptTemplate) -> Result<Self, TemplateError> {
+        self.add(template)?;
+        Ok(self)
+    }
+
+    /// The template of the prompt named `id`
+    pub fn get(&self, id: &str) -> Option<&PromptTemplate> {
+        self.templates.iter().find(|template| template.id == id)
+    }
+
+    /// The prompts of the templates, in the order they were added
+    pub fn prompts(&self) -> Vec<Prompt> {
+        self.templates.iter().map(PromptTemplate::prompt).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::prompt::PromptArgumentTemplate;
+
+    #[test]
+    fn test_templates_compiled_on_add() {
+        let mut templates = PromptTemplates::new();
+        let greet = PromptTemplate::new("greet", "Hello {name}");
+        assert_eq!(
+            templates.add(greet.clone()),
+            Err(TemplateError::UndeclaredArgument("name".to_string()))
+        );
+        assert!(templates.get("greet").is_none());
+
+        let greet = greet.with_argument(PromptArgumentTemplate::new("name"));
+        templates.add(greet.clone()).unwrap();
+        let compiled = templates.get("greet").unwrap().compile().unwrap();
+        assert!(std::ptr::eq(compiled, greet.compile().unwrap()));
+
+        // Adding a template again replaces it
+        templates.add(greet).unwrap();
+        assert_eq!(templates.prompts().len(), 1);
+    }
+}
@@ -1,31 +1,43 @@
 use std::{
+    collections::HashMap,
     future::Future,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
 use mcp_core::{
     content::Content,
-    handler::{PromptError, ResourceError, ToolError},
+    handler::{PromptError, ResourceError, ResourceTemplateHandler, ToolError},
     prompt::{Prompt, PromptMessage, PromptMessageRole},
     protocol::{
-        CallToolResult, GetPromptResult, Implementation, InitializeResult, JsonRpcRequest,
-        JsonRpcResponse, ListPromptsResult, ListResourcesResult, ListToolsResult,
-        PromptsCapability, ReadResourceResult, ResourcesCapability, ServerCapabilities,
-        ToolsCapability,
+        ArgumentInfo, CallToolResult, CompleteRequest, CompleteResult, Completion,
+        CompletionsCapability, EmptyResult, GetPromptResult, Implementation, InitializeResult,
+        JsonRpcRequest, JsonRpcResponse, ListPromptsResult, ListResourceTemplatesResult,
+        ListResourcesResult, ListToolsResult, LoggingCapability, PromptsCapability,
+        ReadResourceResult, Reference, RequestId, ResourcesCapability, ServerCapabilities,
+        SetLevelRequest, SubscribeRequest, ToolsCapability, UnsubscribeRequest,
     },
-    ResourceContents,
+    resource::{Resource, DEFAULT_MIME_TYPE},
+    tool::Tool,
+    ProtocolVersion, ResourceContents, ResourceTemplate,
 };
 use serde_json::Value;
 use tower_service::Service;
 
-use crate::{BoxError, RouterError};
+use crate::{
+    pagination::{request_cursor, Page, DEFAULT_PAGE_SIZE},
+    BoxError, ListChangedNotifier, OutputSchemas, PromptTemplates, RequestContext,
+    ResourceSubscriptions, RouterError,
+};
 
 /// Builder for configuring and constructing capabilities
 pub struct CapabilitiesBuilder {
     tools: Option<ToolsCapability>,
     prompts: Option<PromptsCapability>,
     resources: Option<ResourcesCapability>,
+    logging: Option<LoggingCapability>,
+    completions: Option<CompletionsCapability>,
 }
 
 impl Default for CapabilitiesBuilder {
@@ -40,6 +52,8 @@ impl CapabilitiesBuilder {
             tools: None,
             prompts: None,
             resources: None,
+            logging: None,
+            completions: None,
         }
     }
 
@@ -68,6 +82,20 @@ impl CapabilitiesBuilder {
         self
     }
 
+    /// Enable logging capability, letting the client pick the level of the log messages
+    /// it receives, see [`ClientLoggingLayer`](crate::logging::ClientLoggingLayer)
+    pub fn with_logging(mut self) -> Self {
+        self.logging = Some(LoggingCapability {});
+        self
+    }
+
+    /// Enable completions capability, letting clients ask for suggested prompt and
+    /// resource template arguments, see [`Router::complete`]
+    pub fn with_completions(mut self) -> Self {
+        self.completions = Some(CompletionsCapability {});
+        self
+    }
+
     /// Build the router with automatic capability inference
     pub fn build(self) -> ServerCapabilities {
         // Create capabilities based on what's configured
@@ -75,6 +103,8 @@ impl CapabilitiesBuilder {
             tools: self.tools,
             prompts: self.prompts,
             resources: self.resources,
+            logging: self.logging,
+            completions: self.completions,
         }
     }
 }
@@ -90,16 +120,227 @@ pub trait Router: Send + Sync + 'static {
         tool_name: &str,
         arguments: Value,
     ) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>>;
+    /// Call a tool, returning its full result. Tools that declare an output schema must
+    /// return structured content matching it, which requires overriding this method, see
+    /// [`CallToolResult::structured`]. Defaults to the content from `call_tool`.
+    fn call_tool_result(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+    ) -> Pin<Box<dyn Future<Output = Result<CallToolResult, ToolError>> + Send + 'static>> {
+        let call = self.call_tool(tool_name, arguments);
+        Box::pin(async move { call.await.map(CallToolResult::success) })
+    }
     fn list_resources(&self) -> impl Future<Output = Vec<mcp_core::resource::Resource>> + Send;
     fn read_resource(
         &self,
         uri: &str,
     ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>>;
+    /// Read a resource, returning its contents as text or binary data, possibly in several
+    /// parts (e.g. the files of a directory). Parts without a MIME type get the one their
+    /// resource is listed with. Defaults to the text from `read_resource`.
+    fn read_resource_contents(
+        &self,
+        uri: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<ResourceContents>, ResourceError>> + Send + 'static>>
+    {
+        let read = self.read_resource(uri);
+        let uri = uri.to_string();
+        Box::pin(async move { Ok(vec![ResourceContents::text(uri, read.await?)]) })
+    }
+    /// Notifies clients of changes to the tools, prompts or resources, for routers that
+    /// advertise `list_changed`. Clients are registered with it as they initialize.
+    fn list_changed_notifier(&self) -> Option<&ListChangedNotifier> {
+        None
+    }
+    /// The subscriptions to resource changes, required for routers that advertise
+    /// `subscribe` in their resources capability
+    fn resource_subscriptions(&self) -> Option<&ResourceSubscriptions> {
+        None
+    }
+    /// Handlers for the resource templates this router serves, used by the default
+    /// implementations of `list_resource_templates` and `read_resource_template`
+    fn resource_template_handlers(&self) -> Vec<Arc<dyn ResourceTemplateHandler>> {
+        Vec::new()
+    }
+    fn list_resource_templates(&self) -> impl Future<Output = Vec<ResourceTemplate>> + Send {
+        let templates = self
+            .resource_template_handlers()
+            .iter()
+            .map(|handler| handler.resource_template())
+            .collect();
+        async move { templates }
+    }
+    /// Read a resource that `read_resource` doesn't know about but whose URI matches
+    /// `uri_template`, one of the templates from `list_resource_templates`. `variables`
+    /// holds the values of the template variables found in the URI.
+    fn read_resource_template(
+        &self,
+        uri_template: &str,
+        uri: &str,
+        variables: HashMap<String, String>,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+        let handler = self
+            .resource_template_handlers()
+            .into_iter()
+            .find(|handler| handler.template() == uri_template);
+        let uri = uri.to_string();
+        Box::pin(async move {
+            let handler = handler.ok_or(ResourceError::NotFound(uri))?;
+            let params = serde_json::to_value(variables)
+                .map_err(|e| ResourceError::ExecutionError(e.to_string()))?;
+            handler
+                .get(params)
+                .await
+                .map_err(|e| ResourceError::ExecutionError(e.to_string()))
+        })
+    }
+    /// Suggest values for an argument of a prompt or resource template as the user types
+    /// it, for routers that advertise completions. `argument.value` holds what was typed
+    /// so far. Resource template variables are completed by the template's handler,
+    /// nothing is suggested for prompts unless overridden.
+    fn complete(
+        &self,
+        reference: &Reference,
+        argument: &ArgumentInfo,
+    ) -> Pin<Box<dyn Future<Output = Result<Completion, RouterError>> + Send + 'static>> {
+        let handler = match reference {
+            Reference::Resource { uri } => self
+                .resource_template_handlers()
+                .into_iter()
+                .find(|handler| handler.template() == uri),
+            Reference::Prompt { .. } => None,
+        };
+        let argument = argument.clone();
+        Box::pin(async move {
+            let values = match handler {
+                Some(handler) => handler.complete(&argument.name, &argument.value).await,
+                None => Vec::new(),
+            };
+            Ok(Completion::new(values))
+        })
+    }
     fn list_prompts(&self) -> impl Future<Output = Vec<Prompt>> + Send;
-    fn get_prompt(&self, prompt_name: &str, params: &Value) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>>;
+    /// The number of tools, resources, resource templates or prompts the list handlers
+    /// return per page
+    fn page_size(&self) -> usize {
+        DEFAULT_PAGE_SIZE
+    }
+    /// The page of tools at `cursor`, the first page if it is None. Defaults to cutting
+    /// pages out of `list_tools`, override it to page through a source that has cursors
+    /// of its own.
+    fn list_tools_page(
+        &self,
+        cursor: Option<String>,
+    ) -> impl Future<Output = Result<Page<Tool>, RouterError>> + Send {
+        async move {
+            let tools = self.list_tools().await;
+            Page::from_list(tools, "tools", cursor.as_deref(), self.page_size())
+        }
+    }
+    /// The page of resources at `cursor`, see `list_tools_page`
+    fn list_resources_page(
+        &self,
+        cursor: Option<String>,
+    ) -> impl Future<Output = Result<Page<Resource>, RouterError>> + Send {
+        async move {
+            let resources = self.list_resources().await;
+            Page::from_list(resources, "resources", cursor.as_deref(), self.page_size())
+        }
+    }
+    /// The page of resource templates at `cursor`, see `list_tools_page`
+    fn list_resource_templates_page(
+        &self,
+        cursor: Option<String>,
+    ) -> impl Future<Output = Result<Page<ResourceTemplate>, RouterError>> + Send {
+        async move {
+            let templates = self.list_resource_templates().await;
+            Page::from_list(
+                templates,
+                "resource_templates",
+                cursor.as_deref(),
+                self.page_size(),
+            )
+        }
+    }
+    /// The page of prompts at `cursor`, see `list_tools_page`. The prompts of
+    /// `prompt_templates` follow those of `list_prompts`.
+    fn list_prompts_page(
+        &self,
+        cursor: Option<String>,
+    ) -> impl Future<Output = Result<Page<Prompt>, RouterError>> + Send {
+        async move {
+            let mut prompts = self.list_prompts().await;
+            if let Some(templates) = self.prompt_templates() {
+                prompts.extend(templates.prompts());
+            }
+            Page::from_list(prompts, "prompts", cursor.as_deref(), self.page_size())
+        }
+    }
+    fn get_prompt(
+        &self,
+        prompt_name: &str,
+        params: &Value,
+    ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>>;
+    /// Templates for the prompts this router serves, listed along with `list_prompts` and
+    /// rendered by the default implementation of `get_prompt_result`
+    fn prompt_templates(&self) -> Option<&PromptTemplates> {
+        None
+    }
+    /// Get a prompt as a full result, which may hold several messages from both roles with
+    /// images or embedded resources, as used for few-shot prompts. A `Vec<PromptMessage>`
+    /// converts into a result with `into()`. Defaults to a single user message rendered
+    /// from the prompt's template, or else made of the text from `get_prompt` with the
+    /// `{argument}` placeholders filled in, which is also its description.
+    fn get_prompt_result(
+        &self,
+        prompt_name: &str,
+        params: &Value,
+    ) -> Pin<Box<dyn Future<Output = Result<GetPromptResult, PromptError>> + Send + 'static>> {
+        let arguments = params
+            .get("arguments")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+        let template = self
+            .prompt_templates()
+            .and_then(|templates| templates.get(prompt_name))
+            .cloned();
+
+        if let Some(template) = template {
+            return Box::pin(async move {
+                let text = template.render(&arguments)?;
+                let mut result = GetPromptResult::from(vec![PromptMessage::new_text(
+                    PromptMessageRole::User,
+                    text,
+                )]);
+                result.description = template.description;
+                Ok(result)
+            });
+        }
+
+        let prompt = self.get_prompt(prompt_name, params);
+        Box::pin(async move {
+            let description = prompt.await?;
+
+            // Validate the prompt description length
+            if description.len() > 10000 {
+                return Err(PromptError::InternalError(
+                    "Prompt description exceeds maximum allowed length".into(),
+                ));
+            }
+
+            let description_filled = fill_placeholders(&description, &arguments);
+            let messages = vec![PromptMessage::new_text(
+                PromptMessageRole::User,
+                description_filled.clone(),
+            )];
+            Ok(GetPromptResult::from(messages).with_description(description_filled))
+        })
+    }
 
     // Helper method to create base response
-    fn create_response(&self, id: Option<u64>) -> JsonRpcResponse {
+    fn create_response(&self, id: Option<RequestId>) -> JsonRpcResponse {
         JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
             id,
@@ -113,16 +354,42 @@ pub trait Router: Send + Sync + 'static {
         req: JsonRpcRequest,
     ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
         async move {
+            // Use the client's version if we support it, otherwise propose our latest and
+            // let the client decide whether it can work with that
+            let protocol_version = req
+                .params
+                .as_ref()
+                .and_then(|params| params.get("protocolVersion"))
+                .and_then(Value::as_str)
+                .map_or(ProtocolVersion::LATEST, ProtocolVersion::negotiate);
+
             let result = InitializeResult {
-                protocol_version: "2024-11-05".to_string(),
+                protocol_version: protocol_version.to_string(),
                 capabilities: self.capabilities().clone(),
                 server_info: Implementation {
                     name: self.name(),
                     version: env!("CARGO_PKG_VERSION").to_string(),
                 },
                 instructions: self.instructions(),
+                meta: None,
             };
 
+            if let Some(context) = RequestContext::current() {
+                // A client that announces no capabilities supports none of them
+                let capabilities = req
+                    .params
+                    .as_ref()
+                    .and_then(|params| params.get("capabilities"))
+                    .and_then(|capabilities| serde_json::from_value(capabilities.clone()).ok())
+                    .unwrap_or_default();
+                context.peer().set_client_capabilities(capabilities);
+                context.peer().set_protocol_version(protocol_version);
+
+                if let Some(notifier) = self.list_changed_notifier() {
+                    notifier.register(context.peer());
+                }
+            }
+
             let mut response = self.create_response(req.id);
             response.result =
                 Some(serde_json::to_value(result).map_err(|e| {
@@ -133,16 +400,60 @@ pub trait Router: Send + Sync + 'static {
         }
     }
 
+    fn handle_ping(
+        &self,
+        req: JsonRpcRequest,
+    ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
+        async move {
+            let mut response = self.create_response(req.id);
+            response.result =
+                Some(serde_json::to_value(EmptyResult::default()).map_err(|e| {
+                    RouterError::Internal(format!("JSON serialization error: {}", e))
+                })?);
+
+            Ok(response)
+        }
+    }
+
+    fn handle_set_level(
+        &self,
+        req: JsonRpcRequest,
+    ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
+        async move {
+            let params = req
+                .params
+                .ok_or_else(|| RouterError::InvalidParams("Missing parameters".into()))?;
+
+            let SetLevelRequest { level, .. } = serde_json::from_value(params)
+                .map_err(|e| RouterError::InvalidParams(format!("Invalid log level: {}", e)))?;
+
+            // The level applies to the connection the request came in on
+            if let Some(context) = RequestContext::current() {
+                context.peer().set_log_level(level);
+            }
+
+            let mut response = self.create_response(req.id);
+            response.result =
+                Some(serde_json::to_value(EmptyResult::default()).map_err(|e| {
+                    RouterError::Internal(format!("JSON serialization error: {}", e))
+                })?);
+
+            Ok(response)
+        }
+    }
+
     fn handle_tools_list(
         &self,
         req: JsonRpcRequest,
     ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
         async move {
-            let tools = self.list_tools().await;
+            let cursor = request_cursor(req.params.as_ref())?;
+            let page = self.list_tools_page(cursor).await?;
 
             let result = ListToolsResult {
-                tools,
-                next_cursor: None,
+                tools: page.items,
+                next_cursor: page.next_cursor,
+                meta: None,
             };
             let mut response = self.create_response(req.id);
             response.result =
@@ -157,6 +468,7 @@ pub trait Router: Send + Sync + 'static {
     fn handle_tools_call(
         &self,
         req: JsonRpcRequest,
+        output_schemas: &OutputSchemas,
     ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
         async move {
             let params = req
@@ -170,15 +482,13 @@ pub trait Router: Send + Sync + 'static {
 
             let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
 
-            let result = match self.call_tool(name, arguments).await {
-                Ok(result) => CallToolResult {
-                    content: result,
-                    is_error: None,
-                },
-                Err(err) => CallToolResult {
-                    content: vec![Content::text(err.to_string())],
-                    is_error: Some(true),
-                },
+            let result = match self.call_tool_result(name, arguments).await {
+                Ok(result) if result.is_error != Some(true) => {
+                    self.check_structured_content(name, result, output_schemas)
+                        .await
+                }
+                Ok(result) => result,
+                Err(err) => CallToolResult::error(vec![Content::text(err.to_string())]),
             };
 
             let mut response = self.create_response(req.id);
@@ -191,16 +501,56 @@ pub trait Router: Send + Sync + 'static {
         }
     }
 
+    /// Check that a tool declaring an output schema returned structured content matching
+    /// it, turning the result into an error if not. Clients rely on the schema to parse the
+    /// content, so a mismatch is a bug in the tool.
+    fn check_structured_content(
+        &self,
+        name: &str,
+        result: CallToolResult,
+        output_schemas: &OutputSchemas,
+    ) -> impl Future<Output = CallToolResult> + Send {
+        async move {
+            let tools_version = self
+                .list_changed_notifier()
+                .map(ListChangedNotifier::tools_version);
+            let output_schema = match output_schemas.get(tools_version, name) {
+                Some(schema) => schema,
+                None => output_schemas.rebuild(tools_version, self.list_tools().await, name),
+            };
+            let Some(output_schema) = output_schema else {
+                return result;
+            };
+
+            let checked = match &result.structured_content {
+                Some(content) => output_schema.validate(content),
+                None => Err("no structured content was returned".to_string()),
+            };
+            match checked {
+                Ok(()) => result,
+                Err(e) => {
+                    tracing::error!(tool = name, error = %e, "Invalid structured tool output");
+                    CallToolResult::error(vec![Content::text(format!(
+                        "Tool output does not match its output schema: {}",
+                        e
+                    ))])
+                }
+            }
+        }
+    }
+
     fn handle_resources_list(
         &self,
         req: JsonRpcRequest,
     ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
         async move {
-            let resources = self.list_resources().await;
+            let cursor = request_cursor(req.params.as_ref())?;
+            let page = self.list_resources_page(cursor).await?;
 
             let result = ListResourcesResult {
-                resources,
-                next_cursor: None,
+                resources: page.items,
+                next_cursor: page.next_cursor,
+                meta: None,
             };
             let mut response = self.create_response(req.id);
             response.result =
@@ -226,14 +576,62 @@ pub trait Router: Send + Sync + 'static {
                 .and_then(Value::as_str)
                 .ok_or_else(|| RouterError::InvalidParams("Missing resource URI".into()))?;
 
-            let contents = self.read_resource(uri).await.map_err(RouterError::from)?;
+            // Concrete resources take precedence, templates are only tried for unknown URIs
+            let mut contents = match self.read_resource_contents(uri).await {
+                Ok(mut contents) => {
+                    // Fill in the MIME types the resources are listed with
+                    let resources = self.list_resources().await;
+                    for part in contents
+                        .iter_mut()
+                        .filter(|part| part.mime_type().is_none())
+                    {
+                        if let Some(resource) = resources.iter().find(|r| r.uri == part.uri()) {
+                            *part = part.clone().with_mime_type(resource.mime_type.clone());
+                        }
+                    }
+                    contents
+                }
+                Err(ResourceError::NotFound(message)) => {
+                    let Some((template, variables)) = self
+                        .list_resource_templates()
+                        .await
+                        .into_iter()
+                        .find_map(|template| {
+                            let variables = template.match_uri(uri)?;
+                            Some((template, variables))
+                        })
+                    else {
+                        return Err(RouterError::ResourceNotFound(message));
+                    };
+
+                    let text = self
+                        .read_resource_template(&template.uri_template, uri, variables)
+                        .await
+                        .map_err(RouterError::from)?;
+                    let contents = ResourceContents::text(uri, text);
+                    match template.mime_type {
+                        Some(mime_type) => vec![contents.with_mime_type(mime_type)],
+                        None => vec![contents],
+                    }
+                }
+                Err(e) => return Err(RouterError::from(e)),
+            };
+
+            // Whatever is still untyped gets the generic type of its kind
+            for part in contents
+                .iter_mut()
+                .filter(|part| part.mime_type().is_none())
+            {
+                let mime_type = match part {
+                    ResourceContents::TextResourceContents { .. } => DEFAULT_MIME_TYPE,
+                    ResourceContents::BlobResourceContents { .. } => "application/octet-stream",
+                };
+                *part = part.clone().with_mime_type(mime_type);
+            }
 
             let result = ReadResourceResult {
-                contents: vec![ResourceContents::TextResourceContents {
-                    uri: uri.to_string(),
-                    mime_type: Some("text/plain".to_string()),
-                    text: contents,
-                }],
+                contents,
+                meta: None,
             };
 
             let mut response = self.create_response(req.id);
@@ -246,14 +644,131 @@ pub trait Router: Send + Sync + 'static {
         }
     }
 
+    fn handle_resources_subscribe(
+        &self,
+        req: JsonRpcRequest,
+    ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
+        async move {
+            let params = req
+                .params
+                .ok_or_else(|| RouterError::InvalidParams("Missing parameters".into()))?;
+
+            let SubscribeRequest { uri, .. } = serde_json::from_value(params)
+                .map_err(|_| RouterError::InvalidParams("Missing resource URI".into()))?;
+
+            let subscriptions = self.resource_subscriptions().ok_or_else(|| {
+                RouterError::Internal("Router does not track resource subscriptions".into())
+            })?;
+            // Subscriptions belong to the connection the request came in on
+            let context = RequestContext::current().ok_or_else(|| {
+                RouterError::Internal("Subscribing requires a client connection".into())
+            })?;
+            subscriptions.subscribe(context.peer(), &uri);
+
+            let mut response = self.create_response(req.id);
+            response.result =
+                Some(serde_json::to_value(EmptyResult::default()).map_err(|e| {
+                    RouterError::Internal(format!("JSON serialization error: {}", e))
+                })?);
+
+            Ok(response)
+        }
+    }
+
+    fn handle_resources_unsubscribe(
+        &self,
+        req: JsonRpcRequest,
+    ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
+        async move {
+            let params = req
+                .params
+                .ok_or_else(|| RouterError::InvalidParams("Missing parameters".into()))?;
+
+            let UnsubscribeRequest { uri, .. } = serde_json::from_value(params)
+                .map_err(|_| RouterError::InvalidParams("Missing resource URI".into()))?;
+
+            if let (Some(subscriptions), Some(context)) =
+                (self.resource_subscriptions(), RequestContext::current())
+            {
+                subscriptions.unsubscribe(context.peer(), &uri);
+            }
+
+            let mut response = self.create_response(req.id);
+            response.result =
+                Some(serde_json::to_value(EmptyResult::default()).map_err(|e| {
+                    RouterError::Internal(format!("JSON serialization error: {}", e))
+                })?);
+
+            Ok(response)
+        }
+    }
+
+    fn handle_resource_templates_list(
+        &self,
+        req: JsonRpcRequest,
+    ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
+        async move {
+            let cursor = request_cursor(req.params.as_ref())?;
+            let page = self.list_resource_templates_page(cursor).await?;
+
+            let result = ListResourceTemplatesResult {
+                resource_templates: page.items,
+                next_cursor: page.next_cursor,
+                meta: None,
+            };
+            let mut response = self.create_response(req.id);
+            response.result =
+                Some(serde_json::to_value(result).map_err(|e| {
+                    RouterError::Internal(format!("JSON serialization error: {}", e))
+                })?);
+
+            Ok(response)
+        }
+    }
+
+    fn handle_completion_complete(
+        &self,
+        req: JsonRpcRequest,
+    ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
+        async move {
+            let params: CompleteRequest =
+                serde_json::from_value(req.params.clone().unwrap_or_default())
+                    .map_err(|e| RouterError::InvalidParams(e.to_string()))?;
+
+            let completion = self.complete(&params.reference, &params.argument).await?;
+            // Routers overriding `complete` may not have capped the values
+            let completion = if completion.values.len() > Completion::MAX_VALUES {
+                Completion::new(completion.values)
+            } else {
+                completion
+            };
+
+            let mut response = self.create_response(req.id);
+            response.result = Some(
+                serde_json::to_value(CompleteResult {
+                    completion,
+                    meta: None,
+                })
+                .map_err(|e| RouterError::Internal(format!("JSON serialization error: {}", e)))?,
+            );
+
+            Ok(response)
+        }
+    }
+
     fn handle_prompts_list(
         &self,
         req: JsonRpcRequest,
     ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
         async move {
-            let prompts = self.list_prompts().await;
+            let cursor = request_cursor(req.params.as_ref())?;
+            let page = self.list_prompts_page(cursor).await?;
 
-            let result = ListPromptsResult { prompts };
+            let result = ListPromptsResult {
+                prompts: page.items,
+                next_cursor: page.next_cursor,
+                meta: None,
+            };
 
             let mut response = self.create_response(req.id);
             response.result =
@@ -287,47 +802,46 @@ pub trait Router: Send + Sync + 'static {
                 .and_then(Value::as_object)
                 .ok_or_else(|| RouterError::InvalidParams("Missing arguments object".into()))?;
 
-            // Fetch the prompt definition first
-            let prompt = self
-                .list_prompts()
-                .await
-                .into_iter()
-                .find(|p| p.name == prompt_name)
-                .ok_or_else(|| {
-                    RouterError::PromptNotFound(format!("Prompt '{}' not found", prompt_name))
-                })?;
-
-            // Validate required arguments
-            if let Some(args) = &prompt.arguments {
-                for arg in args {
-                    if arg.required.is_some()
-                        && arg.required.unwrap()
-                        && (!arguments.contains_key(&arg.name)
-                            || arguments
+            // Templates check their own arguments as they are rendered, other prompts are
+            // checked against their definition
+            let is_template = self
+                .prompt_templates()
+                .is_some_and(|templates| templates.get(prompt_name).is_some());
+            if !is_template {
+                let prompt = self
+                    .list_prompts()
+                    .await
+                    .into_iter()
+                    .find(|p| p.name == prompt_name)
+                    .ok_or_else(|| {
+                        RouterError::PromptNotFound(format!("Prompt '{}' not found", prompt_name))
+                    })?;
+
+                // Validate required arguments
+                if let Some(args) = &prompt.arguments {
+                    for arg in args {
+                        if arg.required.is_some()
+                            && arg.required.unwrap()
+                            && arguments
                                 .get(&arg.name)
-                                .and_then(Value::as_str)
-                                .is_none_or(str::is_empty))
-                    {
-                        return Err(RouterError::InvalidParams(format!(
-                            "Missing required argument: '{}'",
-                            arg.name
-                        )));
+                                .is_none_or(|value| value.is_null() || value.as_str() == Some(""))
+                        {
+                            return Err(RouterError::InvalidParams(format!(
+                                "Missing required argument: '{}'",
+                                arg.name
+                            )));
+                        }
                     }
                 }
             }
 
-            // Now get the prompt content
-            let description = self
-                .get_prompt(prompt_name, &params)
-                .await
-                .map_err(|e| RouterError::Internal(e.to_string()))?;
-
             // Validate prompt arguments for potential security issues from user text input
             // Checks:
-            // - Prompt must be less than 10000 total characters
             // - Argument keys must be less than 1000 characters
             // - Argument values must be less than 1000 characters
-            // - Dangerous patterns, eg "../", "//", "\\\\", "<script>", "{{", "}}"
+            // - Dangerous patterns, eg "../", "//", "\\\\", "<script>"
+            // Values are never read as template text or placeholders, so braces in them are
+            // inserted as they are
             for (key, value) in arguments.iter() {
                 // Check for empty or overly long keys/values
                 if key.is_empty() || key.len() > 1000 {
@@ -336,7 +850,11 @@ pub trait Router: Send + Sync + 'static {
                     ));
                 }
 
-                let value_str = value.as_str().unwrap_or_default();
+                // Typed arguments may be sent as JSON values, check their text form
+                let value_str = match value {
+                    Value::String(text) => text.clone(),
+                    value => value.to_string(),
+                };
                 if value_str.len() > 1000 {
                     return Err(RouterError::InvalidParams(
                         "Argument values must not exceed 1000 characters".into(),
@@ -344,7 +862,7 @@ pub trait Router: Send + Sync + 'static {
                 }
 
                 // Check for potentially dangerous patterns
-                let dangerous_patterns = ["../", "//", "\\\\", "<script>", "{{", "}}"];
+                let dangerous_patterns = ["../", "//", "\\\\", "<script>"];
                 for pattern in dangerous_patterns {
                     if key.contains(pattern) || value_str.contains(pattern) {
                         return Err(RouterError::InvalidParams(format!(
@@ -355,43 +873,47 @@ pub trait Router: Send + Sync + 'static {
                 }
             }
 
-            // Validate the prompt description length
-            if description.len() > 10000 {
-                return Err(RouterError::Internal(
-                    "Prompt description exceeds maximum allowed length".into(),
-                ));
-            }
-
-            // Create a mutable copy of the description to fill in arguments
-            let mut description_filled = description.clone();
-
-            // Replace each argument placeholder with its value from the arguments object
-            for (key, value) in arguments {
-                let placeholder = format!("{{{}}}", key);
-                description_filled =
-                    description_filled.replace(&placeholder, value.as_str().unwrap_or_default());
-            }
-
-            let messages = vec![PromptMessage::new_text(
-                PromptMessageRole::User,
-                description_filled.to_string(),
-            )];
+            // Now get the prompt content
+            let result =
+                self.get_prompt_result(prompt_name, &params)
+                    .await
+                    .map_err(|e| match e {
+                        PromptError::InvalidParameters(message) => {
+                            RouterError::InvalidParams(message)
+                        }
+                        e => RouterError::Internal(e.to_string()),
+                    })?;
 
             // Build the final response
             let mut response = self.create_response(req.id);
-            response.result = Some(
-                serde_json::to_value(GetPromptResult {
-                    description: Some(description_filled),
-                    messages,
-                })
-                .map_err(|e| RouterError::Internal(format!("JSON serialization error: {}", e)))?,
-            );
+            response.result =
+                Some(serde_json::to_value(result).map_err(|e| {
+                    RouterError::Internal(format!("JSON serialization error: {}", e))
+                })?);
             Ok(response)
         }
     }
 }
 
-pub struct RouterService<T>(pub T);
+/// Serves a [`Router`] as a service the [`Server`](crate::Server) can drive
+#[derive(Clone)]
+pub struct RouterService<T> {
+    router: T,
+    output_schemas: OutputSchemas,
+}
+
+impl<T> RouterService<T> {
+    pub fn new(router: T) -> Self {
+        Self {
+            router,
+            output_schemas: OutputSchemas::new(),
+        }
+    }
+
+    pub fn router(&self) -> &T {
+        &self.router
+    }
+}
 
 impl<T> Service<JsonRpcRequest> for RouterService<T>
 where
@@ -406,15 +928,37 @@ where
     }
 
     fn call(&mut self, req: JsonRpcRequest) -> Self::Future {
-        let this = self.0.clone();
+        let this = self.router.clone();
+        let output_schemas = self.output_schemas.clone();
 
         Box::pin(async move {
+            let id = req.id.clone();
             let result = match req.method.as_str() {
                 "initialize" => this.handle_initialize(req).await,
+                "ping" => this.handle_ping(req).await,
+                "logging/setLevel" if this.capabilities().logging.is_some() => {
+                    this.handle_set_level(req).await
+                }
                 "tools/list" => this.handle_tools_list(req).await,
-                "tools/call" => this.handle_tools_call(req).await,
+                "tools/call" => this.handle_tools_call(req, &output_schemas).await,
                 "resources/list" => this.handle_resources_list(req).await,
                 "resources/read" => this.handle_resources_read(req).await,
+                "resources/templates/list" => this.handle_resource_templates_list(req).await,
+                "resources/subscribe" | "resources/unsubscribe"
+                    if this
+                        .capabilities()
+                        .resources
+                        .is_none_or(|resources| resources.subscribe != Some(true)) =>
+                {
+                    let mut response = this.create_response(req.id);
+                    response.error = Some(RouterError::MethodNotFound(req.method).into());
+                    Ok(response)
+                }
+                "resources/subscribe" => this.handle_resources_subscribe(req).await,
+                "resources/unsubscribe" => this.handle_resources_unsubscribe(req).await,
+                "completion/complete" if this.capabilities().completions.is_some() => {
+                    this.handle_completion_complete(req).await
+                }
                 "prompts/list" => this.handle_prompts_list(req).await,
                 "prompts/get" => this.handle_prompts_get(req).await,
                 _ => {
@@ -424,7 +968,681 @@ where
                 }
             };
 
-            result.map_err(BoxError::from)
+            // Answer router errors with their own JSON-RPC error code
+            Ok(result.unwrap_or_else(|e| {
+                let mut response = this.create_response(id);
+                response.error = Some(e.into());
+                response
+            }))
         })
     }
 }
+
+/// Replace each `{argument}` placeholder in `text` with the argument's value, strings as
+/// they are and other values as JSON. Placeholders of missing arguments are left as they
+/// are, and the text is scanned once, so placeholders in values are never replaced.
+fn fill_placeholders(text: &str, arguments: &serde_json::Map<String, Value>) -> String {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after
+            .find('}')
+            .and_then(|end| Some((end, arguments.get(&after[..end])?)));
+        match value {
+            Some((end, value)) => {
+                match value {
+                    Value::String(text) => filled.push_str(text),
+                    value => filled.push_str(&value.to_string()),
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use mcp_core::{
+        handler::ToolResult,
+        prompt::{ArgumentType, PromptArgument, PromptArgumentTemplate, PromptTemplate},
+        protocol::{ListChanged, INVALID_PARAMS, INVALID_REQUEST},
+    };
+    use serde_json::json;
+
+    /// Serves `logs://{date}{?level}` and nothing else
+    struct LogsTemplate;
+
+    #[async_trait]
+    impl ResourceTemplateHandler for LogsTemplate {
+        fn template(&self) -> &'static str {
+            "logs://{date}{?level}"
+        }
+
+        fn name(&self) -> &'static str {
+            "logs"
+        }
+
+        fn mime_type(&self) -> Option<&'static str> {
+            Some("text/x-log")
+        }
+
+        fn schema(&self) -> Value {
+            json!({ "type": "object" })
+        }
+
+        async fn complete(&self, variable: &str, value: &str) -> Vec<String> {
+            if variable != "level" {
+                return Vec::new();
+            }
+            ["debug", "info", "warn", "error"]
+                .into_iter()
+                .filter(|level| level.starts_with(value))
+                .map(str::to_string)
+                .collect()
+        }
+
+        async fn get(&self, params: Value) -> ToolResult<String> {
+            Ok(format!(
+                "{} {}",
+                params["date"].as_str().unwrap_or_default(),
+                params["level"].as_str().unwrap_or("all")
+            ))
+        }
+    }
+
+    /// A router serving only what each test adds to it
+    #[derive(Clone, Default)]
+    struct TestRouter {
+        // Each tool with the structured output it returns, shared so a test can change them
+        tools: Arc<std::sync::Mutex<Vec<(Tool, Value)>>>,
+        resources: Vec<(Resource, Vec<ResourceContents>)>,
+        templates: Vec<Arc<dyn ResourceTemplateHandler>>,
+        prompts: Vec<(Prompt, Vec<PromptMessage>)>,
+        page_size: Option<usize>,
+        notifier: ListChangedNotifier,
+    }
+
+    impl TestRouter {
+        fn with_tool(self, tool: Tool, output: Value) -> Self {
+            self.tools.lock().unwrap().push((tool, output));
+            self
+        }
+
+        fn with_resource(mut self, resource: Resource, contents: Vec<ResourceContents>) -> Self {
+            self.resources.push((resource, contents));
+            self
+        }
+
+        fn with_template(mut self, template: impl ResourceTemplateHandler + 'static) -> Self {
+            self.templates.push(Arc::new(template));
+            self
+        }
+
+        fn with_prompt(mut self, prompt: Prompt, messages: Vec<PromptMessage>) -> Self {
+            self.prompts.push((prompt, messages));
+            self
+        }
+
+        fn with_page_size(mut self, page_size: usize) -> Self {
+            self.page_size = Some(page_size);
+            self
+        }
+    }
+
+    impl Router for TestRouter {
+        fn name(&self) -> String {
+            "test".to_string()
+        }
+
+        fn instructions(&self) -> Option<String> {
+            None
+        }
+
+        fn capabilities(&self) -> ServerCapabilities {
+            CapabilitiesBuilder::new()
+                .with_tools(true)
+                .with_resources(false, false)
+                .with_prompts(false)
+                .with_completions()
+                .build()
+        }
+
+        async fn list_tools(&self) -> Vec<Tool> {
+            let tools = self.tools.lock().unwrap();
+            tools.iter().map(|(tool, _)| tool.clone()).collect()
+        }
+
+        fn call_tool(
+            &self,
+            tool_name: &str,
+            _arguments: Value,
+        ) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>>
+        {
+            let tool_name = tool_name.to_string();
+            Box::pin(async move { Err(ToolError::NotFound(tool_name)) })
+        }
+
+        fn call_tool_result(
+            &self,
+            tool_name: &str,
+            _arguments: Value,
+        ) -> Pin<Box<dyn Future<Output = Result<CallToolResult, ToolError>> + Send + 'static>>
+        {
+            let tools = self.tools.lock().unwrap();
+            let result = tools
+                .iter()
+                .find(|(tool, _)| tool.name == tool_name)
+                .map(|(_, output)| CallToolResult::structured(output.clone()))
+                .ok_or_else(|| ToolError::NotFound(tool_name.to_string()));
+            Box::pin(async move { result })
+        }
+
+        async fn list_resources(&self) -> Vec<Resource> {
+            self.resources
+                .iter()
+                .map(|(resource, _)| resource.clone())
+                .collect()
+        }
+
+        fn read_resource(
+            &self,
+            uri: &str,
+        ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+            let uri = uri.to_string();
+            Box::pin(async move { Err(ResourceError::NotFound(uri)) })
+        }
+
+        fn read_resource_contents(
+            &self,
+            uri: &str,
+        ) -> Pin<
+            Box<dyn Future<Output = Result<Vec<ResourceContents>, ResourceError>> + Send + 'static>,
+        > {
+            let contents = self
+                .resources
+                .iter()
+                .find(|(resource, _)| resource.uri == uri)
+                .map(|(_, contents)| contents.clone())
+                .ok_or_else(|| ResourceError::NotFound(uri.to_string()));
+            Box::pin(async move { contents })
+        }
+
+        fn list_changed_notifier(&self) -> Option<&ListChangedNotifier> {
+            Some(&self.notifier)
+        }
+
+        fn resource_template_handlers(&self) -> Vec<Arc<dyn ResourceTemplateHandler>> {
+            self.templates.clone()
+        }
+
+        async fn list_prompts(&self) -> Vec<Prompt> {
+            self.prompts
+                .iter()
+                .map(|(prompt, _)| prompt.clone())
+                .collect()
+        }
+
+        fn get_prompt(
+            &self,
+            prompt_name: &str,
+            _params: &Value,
+        ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>> {
+            let prompt_name = prompt_name.to_string();
+            Box::pin(async move { Err(PromptError::NotFound(prompt_name)) })
+        }
+
+        fn get_prompt_result(
+            &self,
+            prompt_name: &str,
+            _params: &Value,
+        ) -> Pin<Box<dyn Future<Output = Result<GetPromptResult, PromptError>> + Send + 'static>>
+        {
+            let result = self
+                .prompts
+                .iter()
+                .find(|(prompt, _)| prompt.name == prompt_name)
+                .map(|(_, messages)| messages.clone().into())
+                .ok_or_else(|| PromptError::NotFound(prompt_name.to_string()));
+            Box::pin(async move { result })
+        }
+
+        fn page_size(&self) -> usize {
+            self.page_size.unwrap_or(DEFAULT_PAGE_SIZE)
+        }
+    }
+
+    /// Serves a `report` prompt from a template and a `classify` prompt from
+    /// `get_prompt`, through the default `get_prompt_result`
+    #[derive(Clone)]
+    struct PromptTemplateRouter {
+        templates: PromptTemplates,
+    }
+
+    impl PromptTemplateRouter {
+        fn new() -> Self {
+            let report = PromptTemplate::new(
+                "report",
+                "Summarize {#if days}the last {days} days of {/if}{service} logs.",
+            )
+            .with_description("Summarize the logs of a service")
+            .with_argument(PromptArgumentTemplate::new("service").required())
+            .with_argument(PromptArgumentTemplate::new("days").with_type(ArgumentType::Integer));
+            Self {
+                templates: PromptTemplates::new().with_template(report).unwrap(),
+            }
+        }
+    }
+
+    impl Router for PromptTemplateRouter {
+        fn name(&self) -> String {
+            "prompts".to_string()
+        }
+
+        fn instructions(&self) -> Option<String> {
+            None
+        }
+
+        fn capabilities(&self) -> ServerCapabilities {
+            CapabilitiesBuilder::new().with_prompts(false).build()
+        }
+
+        async fn list_tools(&self) -> Vec<Tool> {
+            Vec::new()
+        }
+
+        fn call_tool(
+            &self,
+            tool_name: &str,
+            _arguments: Value,
+        ) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>>
+        {
+            let tool_name = tool_name.to_string();
+            Box::pin(async move { Err(ToolError::NotFound(tool_name)) })
+        }
+
+        async fn list_resources(&self) -> Vec<Resource> {
+            Vec::new()
+        }
+
+        fn read_resource(
+            &self,
+            uri: &str,
+        ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+            let uri = uri.to_string();
+            Box::pin(async move { Err(ResourceError::NotFound(uri)) })
+        }
+
+        async fn list_prompts(&self) -> Vec<Prompt> {
+            vec![Prompt::new(
+                "classify",
+                Some("Classify a log line"),
+                Some(vec![PromptArgument {
+                    name: "line".to_string(),
+                    description: None,
+                    required: Some(true),
+                }]),
+            )]
+        }
+
+        fn get_prompt(
+            &self,
+            prompt_name: &str,
+            _params: &Value,
+        ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>> {
+            let prompt = match prompt_name {
+                "classify" => Ok("Classify '{line}' as {level}".to_string()),
+                _ => Err(PromptError::NotFound(prompt_name.to_string())),
+            };
+            Box::pin(async move { prompt })
+        }
+
+        fn prompt_templates(&self) -> Option<&PromptTemplates> {
+            Some(&self.templates)
+        }
+    }
+
+    /// A tool whose structured output must hold `field`
+    fn counting_tool(name: &str, field: &str) -> Tool {
+        Tool::new(name, "Count log lines", json!({ "type": "object" })).with_output_schema(json!({
+            "type": "object",
+            "properties": { field: { "type": "integer" } },
+            "required": [field]
+        }))
+    }
+
+    fn request(method: &str, params: Value) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(RequestId::Number(1.into())),
+            method: method.to_string(),
+            params: Some(params),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initialize_negotiates_version() {
+        let mut service = RouterService::new(TestRouter::default());
+
+        for (requested, expected) in [
+            ("2024-11-05", "2024-11-05"),
+            ("1.0.0", ProtocolVersion::LATEST.as_str()),
+        ] {
+            let response = service
+                .call(request(
+                    "initialize",
+                    json!({ "protocolVersion": requested, "capabilities": {} }),
+                ))
+                .await
+                .unwrap();
+            let result: InitializeResult =
+                serde_json::from_value(response.result.unwrap()).unwrap();
+            assert_eq!(result.protocol_version, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tools_list_pages() {
+        let router = TestRouter::default()
+            .with_tool(counting_tool("count", "lines"), json!({}))
+            .with_tool(counting_tool("miscount", "lines"), json!({}))
+            .with_page_size(1);
+        let mut service = RouterService::new(router);
+
+        let response = service
+            .call(request("tools/list", json!({})))
+            .await
+            .unwrap();
+        let first: ListToolsResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(first.tools[0].name, "count");
+        let cursor = first.next_cursor.unwrap();
+
+        let response = service
+            .call(request("tools/list", json!({ "cursor": cursor })))
+            .await
+            .unwrap();
+        let second: ListToolsResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(second.tools[0].name, "miscount");
+        assert_eq!(second.next_cursor, None);
+
+        let response = service
+            .call(request("tools/list", json!({ "cursor": "1.0" })))
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_with_several_messages() {
+        let router = TestRouter::default().with_prompt(
+            Prompt::new("classify", Some("Classify a log line"), None),
+            vec![
+                PromptMessage::new_text(PromptMessageRole::User, "disk full"),
+                PromptMessage::new_text(PromptMessageRole::Assistant, "error"),
+                PromptMessage::new_text(PromptMessageRole::User, "started"),
+            ],
+        );
+        let mut service = RouterService::new(router);
+
+        let response = service
+            .call(request(
+                "prompts/get",
+                json!({ "name": "classify", "arguments": {} }),
+            ))
+            .await
+            .unwrap();
+        let result: GetPromptResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        let roles: Vec<_> = result.messages.iter().map(|m| m.role.clone()).collect();
+        assert_eq!(
+            roles,
+            vec![
+                PromptMessageRole::User,
+                PromptMessageRole::Assistant,
+                PromptMessageRole::User
+            ]
+        );
+        assert_eq!(result.description, None);
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_validated() {
+        // Both claim to count log lines, but `miscount` returns the wrong shape
+        let router = TestRouter::default()
+            .with_tool(counting_tool("count", "lines"), json!({ "lines": 42 }))
+            .with_tool(
+                counting_tool("miscount", "lines"),
+                json!({ "lines": "many" }),
+            );
+        let mut service = RouterService::new(router);
+
+        let response = service
+            .call(request("tools/call", json!({ "name": "count" })))
+            .await
+            .unwrap();
+        let result: CallToolResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(result.structured_content, Some(json!({ "lines": 42 })));
+        assert_eq!(result.is_error, None);
+
+        let response = service
+            .call(request("tools/call", json!({ "name": "miscount" })))
+            .await
+            .unwrap();
+        let result: CallToolResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(result.structured_content, None);
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_complete_template_variable() {
+        let mut service = RouterService::new(TestRouter::default().with_template(LogsTemplate));
+
+        let response = service
+            .call(request(
+                "completion/complete",
+                json!({
+                    "ref": { "type": "ref/resource", "uri": "logs://{date}{?level}" },
+                    "argument": { "name": "level", "value": "de" }
+                }),
+            ))
+            .await
+            .unwrap();
+        let result: CompleteResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(result.completion.values, vec!["debug"]);
+        assert_eq!(result.completion.has_more, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_resource_templates() {
+        // The readme comes with its logo
+        let router = TestRouter::default()
+            .with_template(LogsTemplate)
+            .with_resource(
+                Resource::new("docs://readme", Some("text/markdown".to_string()), None).unwrap(),
+                vec![
+                    ResourceContents::text("docs://readme", "# Logs"),
+                    ResourceContents::blob("docs://logo", &[0x89, 0x50]),
+                ],
+            );
+        let mut service = RouterService::new(router);
+
+        let response = service
+            .call(request("resources/templates/list", json!({})))
+            .await
+            .unwrap();
+        let result: ListResourceTemplatesResult =
+            serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(result.resource_templates.len(), 1);
+        assert_eq!(
+            result.resource_templates[0].uri_template,
+            "logs://{date}{?level}"
+        );
+
+        let response = service
+            .call(request(
+                "resources/read",
+                json!({ "uri": "logs://2024-01-01?level=warn" }),
+            ))
+            .await
+            .unwrap();
+        let result: ReadResourceResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(
+            result.contents,
+            vec![ResourceContents::TextResourceContents {
+                uri: "logs://2024-01-01?level=warn".to_string(),
+                mime_type: Some("text/x-log".to_string()),
+                text: "2024-01-01 warn".to_string(),
+            }]
+        );
+
+        let response = service
+            .call(request("resources/read", json!({ "uri": "docs://readme" })))
+            .await
+            .unwrap();
+        let result: ReadResourceResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(
+            result.contents,
+            vec![
+                ResourceContents::text("docs://readme", "# Logs").with_mime_type("text/markdown"),
+                ResourceContents::blob("docs://logo", &[0x89, 0x50])
+                    .with_mime_type("application/octet-stream"),
+            ]
+        );
+
+        // URIs matching neither a resource nor a template are still not found
+        let response = service
+            .call(request("resources/read", json!({ "uri": "metrics://cpu" })))
+            .await
+            .unwrap();
+        assert_eq!(response.error.unwrap().code, INVALID_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_output_schemas_follow_tool_changes() {
+        let router =
+            TestRouter::default().with_tool(counting_tool("count", "lines"), json!({ "lines": 1 }));
+        let mut service = RouterService::new(router.clone());
+        let call = || request("tools/call", json!({ "name": "count" }));
+
+        let response = service.call(call()).await.unwrap();
+        let result: CallToolResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(result.is_error, None);
+
+        // The tool now promises words, which only counts once the change is announced
+        router.tools.lock().unwrap()[0].0 = counting_tool("count", "words");
+        let response = service.call(call()).await.unwrap();
+        let result: CallToolResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(result.is_error, None);
+
+        router.notifier.notify(ListChanged::Tools);
+        let response = service.call(call()).await.unwrap();
+        let result: CallToolResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_prompt_templates() {
+        let router = PromptTemplateRouter::new();
+        let compiled = router.templates.get("report").unwrap().compile().unwrap();
+        let mut service = RouterService::new(router.clone());
+
+        let response = service
+            .call(request("prompts/list", json!({})))
+            .await
+            .unwrap();
+        let result: ListPromptsResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        let names: Vec<_> = result.prompts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["classify", "report"]);
+
+        let get = |name: &str, arguments: Value| {
+            request(
+                "prompts/get",
+                json!({ "name": name, "arguments": arguments }),
+            )
+        };
+        let response = service
+            .call(get("report", json!({ "service": "api", "days": "7" })))
+            .await
+            .unwrap();
+        let result: GetPromptResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(
+            result.description.as_deref(),
+            Some("Summarize the logs of a service")
+        );
+        assert_eq!(
+            result.messages,
+            vec![PromptMessage::new_text(
+                PromptMessageRole::User,
+                "Summarize the last 7 days of api logs."
+            )]
+        );
+
+        // Rendering again uses the template compiled when it was added
+        let response = service
+            .call(get("report", json!({ "service": "db" })))
+            .await
+            .unwrap();
+        assert!(response.error.is_none());
+        let report = router.templates.get("report").unwrap();
+        assert!(std::ptr::eq(report.compile().unwrap(), compiled));
+
+        // The template checks its arguments
+        for arguments in [
+            json!({ "days": "7" }),
+            json!({ "service": "api", "days": "a week" }),
+        ] {
+            let response = service.call(get("report", arguments)).await.unwrap();
+            assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+        }
+
+        // Prompts from get_prompt only have their placeholders replaced, once
+        let response = service
+            .call(get(
+                "classify",
+                json!({ "line": "disk {level}", "level": "error" }),
+            ))
+            .await
+            .unwrap();
+        let result: GetPromptResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(
+            result.description.as_deref(),
+            Some("Classify 'disk {level}' as error")
+        );
+        assert_eq!(
+            result.messages,
+            vec![PromptMessage::new_text(
+                PromptMessageRole::User,
+                "Classify 'disk {level}' as error"
+            )]
+        );
+
+        // Any value but null or an empty string counts as given, and is inserted as JSON
+        let response = service
+            .call(get("classify", json!({ "line": 42, "level": true })))
+            .await
+            .unwrap();
+        let result: GetPromptResult = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(
+            result.messages,
+            vec![PromptMessage::new_text(
+                PromptMessageRole::User,
+                "Classify '42' as true"
+            )]
+        );
+        for line in [json!(null), json!("")] {
+            let response = service
+                .call(get("classify", json!({ "line": line })))
+                .await
+                .unwrap();
+            assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use mcp_core::protocol::ResourceUpdatedNotification;
+
+use crate::Peer;
+
+/// Tracks which resources each connected client subscribed to, so that a `Router` can
+/// tell them when a resource changes.
+///
+/// A router that advertises resource subscriptions keeps one of these, hands it out from
+/// [`Router::resource_subscriptions`](crate::Router::resource_subscriptions), and calls
+/// [`ResourceSubscriptions::notify_updated`] whenever the content of a resource changes.
+/// Clones share the same subscriptions, so the router can be cloned per connection.
+#[derive(Clone, Default)]
+pub struct ResourceSubscriptions {
+    sessions: Arc<Mutex<Vec<Session>>>,
+}
+
+struct Session {
+    peer: Peer,
+    uris: HashSet<String>,
+}
+
+impl ResourceSubscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe the client behind `peer` to changes of `uri`
+    pub fn subscribe(&self, peer: &Peer, uri: &str) {
+        let mut sessions = self.sessions();
+        // Forget clients that went away while we are here
+        sessions.retain(|session| !session.peer.is_closed());
+
+        match sessions
+            .iter_mut()
+            .find(|session| session.peer.same_connection(peer))
+        {
+            Some(session) => {
+                session.uris.insert(uri.to_string());
+            }
+            None => sessions.push(Session {
+                peer: peer.clone(),
+                uris: HashSet::from([uri.to_string()]),
+            }),
+        }
+    }
+
+    /// End the subscription of the client behind `peer` to changes of `uri`
+    pub fn unsubscribe(&self, peer: &Peer, uri: &str) {
+        let mut sessions = self.sessions();
+        if let Some(session) = sessions
+            .iter_mut()
+            .find(|session| session.peer.same_connection(peer))
+        {
+            session.uris.remove(uri);
+        }
+        sessions.retain(|session| !session.uris.is_empty());
+    }
+
+    /// Returns true if the client behind `peer` is subscribed to `uri`
+    pub fn is_subscribed(&self, peer: &Peer, uri: &str) -> bool {
+        self.sessions()
+            .iter()
+            .any(|session| session.peer.same_connection(peer) && session.uris.contains(uri))
+    }
+
+    /// Send `notifications/resources/updated` to every client subscribed to `uri`,
+    /// returning how many were notified
+    pub fn notify_updated(&self, uri: &str) -> usize {
+        let mut sessions = self.sessions();
+        sessions.retain(|session| !session.peer.is_closed());
+
+        let notification = ResourceUpdatedNotification {
+            uri: uri.to_string(),
+            meta: None,
+        };
+        sessions
+            .iter()
+            .filter(|session| session.uris.contains(uri))
+            .filter(|session| {
+                session
+                    .peer
+                    .send_notification(ResourceUpdatedNotification::METHOD, &notification)
+                    .is_ok()
+            })
+            .count()
+    }
+
+    fn sessions(&self) -> std::sync::MutexGuard<'_, Vec<Session>> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for ResourceSubscriptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResourceSubscriptions")
+            .field("sessions", &self.sessions().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::protocol::JsonRpcMessage;
+
+    #[test]
+    fn test_notifies_subscribed_sessions_only() {
+        let subscriptions = ResourceSubscriptions::new();
+        let (alice, mut alice_rx) = Peer::new();
+        let (bob, mut bob_rx) = Peer::new();
+
+        subscriptions.subscribe(&alice, "file:///a.txt");
+        subscriptions.subscribe(&bob, "file:///b.txt");
+        assert!(subscriptions.is_subscribed(&alice, "file:///a.txt"));
+        assert!(!subscriptions.is_subscribed(&alice, "file:///b.txt"));
+
+        assert_eq!(subscriptions.notify_updated("file:///a.txt"), 1);
+        match alice_rx.try_recv() {
+            Ok(JsonRpcMessage::Notification(notification)) => {
+                assert_eq!(notification.method, "notifications/resources/updated");
+                assert_eq!(
+                    notification.params,
+                    Some(serde_json::json!({ "uri": "file:///a.txt" }))
+                );
+            }
+            other => panic!("Expected Notification, got {:?}", other),
+        }
+        assert!(bob_rx.try_recv().is_err());
+
+        subscriptions.unsubscribe(&alice, "file:///a.txt");
+        assert_eq!(subscriptions.notify_updated("file:///a.txt"), 0);
+
+        // Sessions whose connection ended are dropped
+        drop(bob_rx);
+        assert_eq!(subscriptions.notify_updated("file:///b.txt"), 0);
+    }
+}
@@ -0,0 +1,471 @@
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures::{Sink, SinkExt, Stream};
+use mcp_core::protocol::JsonRpcMessage;
+use pin_project::pin_project;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::{
+    bytes::{Buf, Bytes, BytesMut},
+    codec::{Decoder, Encoder},
+    io::poll_read_buf,
+};
+
+use crate::TransportError;
+
+/// Default upper bound on the size of a single newline-delimited frame
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 4 * 1024 * 1024;
+
+/// A transport the [`Server`](crate::Server) can be driven by: a stream of incoming
+/// JSON-RPC messages and a sink the server writes its outgoing messages to.
+///
+/// It is implemented for every type that is both such a stream and such a sink, so stdio,
+/// SSE, WebSocket, in-memory or custom transports only need to provide those two halves.
+/// An error yielded by the stream is reported back to the peer as a JSON-RPC error, while
+/// an error from the sink ends the server.
+pub trait ServerTransport:
+    Stream<Item = Result<JsonRpcMessage, TransportError>> + Sink<JsonRpcMessage, Error = TransportError>
+{
+}
+
+impl<T> ServerTransport for T where
+    T: Stream<Item = Result<JsonRpcMessage, TransportError>>
+        + Sink<JsonRpcMessage, Error = TransportError>
+{
+}
+
+/// Codec for newline-delimited JSON-RPC frames.
+///
+/// Decoding yields the raw bytes of each line without the trailing `\n` (or `\r\n`) and
+/// keeps its scan position across calls, so a frame that arrives in many small reads is
+/// only searched once. A frame longer than the configured maximum is reported as
+/// [`TransportError::FrameTooLarge`] and skipped up to its terminating newline, after
+/// which decoding resumes normally. Encoding writes a message as one line of JSON.
+#[derive(Debug, Clone)]
+pub struct JsonRpcFrameCodec {
+    max_frame_size: usize,
+    // Index up to which the buffer has already been searched for a newline
+    next_index: usize,
+    // Set while skipping the remainder of an oversized frame
+    discarding: bool,
+}
+
+impl Default for JsonRpcFrameCodec {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FRAME_SIZE)
+    }
+}
+
+impl JsonRpcFrameCodec {
+    pub fn new(max_frame_size: usize) -> Self {
+        Self {
+            max_frame_size,
+            next_index: 0,
+            discarding: false,
+        }
+    }
+
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+}
+
+impl Decoder for JsonRpcFrameCodec {
+    type Item = Bytes;
+    type Error = TransportError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            let newline = src[self.next_index..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map(|offset| self.next_index + offset);
+
+            match (self.discarding, newline) {
+                (true, Some(end)) => {
+                    // Drop the tail of the oversized frame and carry on with the next one
+                    src.advance(end + 1);
+                    self.discarding = false;
+                    self.next_index = 0;
+                }
+                (true, None) => {
+                    src.clear();
+                    self.next_index = 0;
+                    return Ok(None);
+                }
+                (false, Some(end)) => {
+                    self.next_index = 0;
+                    let mut frame = src.split_to(end + 1);
+                    frame.truncate(end);
+                    if frame.last() == Some(&b'\r') {
+                        frame.truncate(end - 1);
+                    }
+                    if frame.len() > self.max_frame_size {
+                        return Err(TransportError::FrameTooLarge(self.max_frame_size));
+                    }
+                    // Blank lines carry no message
+                    if frame.iter().all(u8::is_ascii_whitespace) {
+                        continue;
+                    }
+                    return Ok(Some(frame.freeze()));
+                }
+                (false, None) => {
+                    if src.len() > self.max_frame_size {
+                        src.clear();
+                        self.next_index = 0;
+                        self.discarding = true;
+                        return Err(TransportError::FrameTooLarge(self.max_frame_size));
+                    }
+                    self.next_index = src.len();
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(frame) = self.decode(src)? {
+            return Ok(Some(frame));
+        }
+
+        // Accept a final frame that is not terminated by a newline
+        self.next_index = 0;
+        if self.discarding || src.iter().all(u8::is_ascii_whitespace) {
+            src.clear();
+            self.discarding = false;
+            return Ok(None);
+        }
+        Ok(Some(src.split().freeze()))
+    }
+}
+
+impl Encoder<JsonRpcMessage> for JsonRpcFrameCodec {
+    type Error = TransportError;
+
+    fn encode(&mut self, item: JsonRpcMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let json = serde_json::to_vec(&item)?;
+        dst.reserve(json.len() + 1);
+        dst.extend_from_slice(&json);
+        dst.extend_from_slice(b"\n");
+        Ok(())
+    }
+}
+
+/// A transport layer that handles newline-delimited JSON-RPC messages over a byte stream
+#[pin_project]
+pub struct ByteTransport<R, W> {
+    #[pin]
+    reader: R,
+    #[pin]
+    writer: W,
+    codec: JsonRpcFrameCodec,
+    // Bytes read from the reader that have not been decoded into a frame yet, kept across
+    // polls so that a partially received line is never lost
+    read_buf: BytesMut,
+    // Encoded outgoing messages that have not been written to the writer yet
+    write_buf: BytesMut,
+    eof: bool,
+}
+
+impl<R, W> ByteTransport<R, W>
+where
+    R: AsyncRead,
+    W: AsyncWrite,
+{
+    pub fn new(reader: R, writer: W) -> Self {
+        Self::with_codec(reader, writer, JsonRpcFrameCodec::default())
+    }
+
+    /// Create a transport that rejects incoming frames larger than `max_frame_size` bytes
+    pub fn with_max_frame_size(reader: R, writer: W, max_frame_size: usize) -> Self {
+        Self::with_codec(reader, writer, JsonRpcFrameCodec::new(max_frame_size))
+    }
+
+    fn with_codec(reader: R, writer: W, codec: JsonRpcFrameCodec) -> Self {
+        Self {
+            reader,
+            writer,
+            codec,
+            read_buf: BytesMut::with_capacity(8 * 1024),
+            write_buf: BytesMut::new(),
+            eof: false,
+        }
+    }
+}
+
+impl<R, W> Stream for ByteTransport<R, W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    type Item = Result<JsonRpcMessage, TransportError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            let frame = if *this.eof {
+                this.codec.decode_eof(this.read_buf)
+            } else {
+                this.codec.decode(this.read_buf)
+            };
+            match frame {
+                Ok(Some(frame)) => return Poll::Ready(Some(parse_frame(frame))),
+                Ok(None) if *this.eof => return Poll::Ready(None),
+                Ok(None) => {}
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+
+            // Need more data, read straight into the persistent buffer
+            match ready!(poll_read_buf(this.reader.as_mut(), cx, this.read_buf)) {
+                Ok(0) => *this.eof = true,
+                Ok(_) => {}
+                Err(e) => return Poll::Ready(Some(Err(TransportError::Io(e)))),
+            }
+        }
+    }
+}
+
+/// Turn one frame into a JSON-RPC message, validating its basic structure
+fn parse_frame(frame: Bytes) -> Result<JsonRpcMessage, TransportError> {
+    // Convert to UTF-8 string
+    let line = String::from_utf8(frame.into())?;
+    // Log incoming message here before serde conversion to
+    // track incomplete chunks which are not valid JSON
+    tracing::info!(json = %line, "incoming message");
+
+    // Parse JSON and validate message format
+    let value = serde_json::from_str::<serde_json::Value>(&line)?;
+
+    // Validate basic JSON-RPC structure. The elements of a batch are validated one by one
+    // while parsing, an invalid element becomes an error for that element only.
+    if !value.is_array() {
+        validate_jsonrpc_object(&value)?;
+    }
+
+    // Now try to parse as proper message
+    Ok(serde_json::from_value::<JsonRpcMessage>(value)?)
+}
+
+/// Check that a single JSON value looks like a JSON-RPC 2.0 message
+fn validate_jsonrpc_object(value: &serde_json::Value) -> Result<(), TransportError> {
+    let obj = value.as_object().ok_or_else(|| {
+        TransportError::InvalidMessage("Message must be a JSON object or an array of them".into())
+    })?;
+
+    // Check jsonrpc version field
+    if !obj.contains_key("jsonrpc") || obj["jsonrpc"] != "2.0" {
+        return Err(TransportError::InvalidMessage(
+            "Missing or invalid jsonrpc version".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+impl<R, W> ByteTransport<R, W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    pub async fn write_message(
+        self: &mut Pin<&mut Self>,
+        msg: JsonRpcMessage,
+    ) -> Result<(), std::io::Error> {
+        self.send(msg).await.map_err(|e| match e {
+            TransportError::Io(e) => e,
+            other => std::io::Error::new(std::io::ErrorKind::InvalidData, other),
+        })
+    }
+
+    /// Write out as much of the pending write buffer as the writer accepts
+    fn poll_write_buf(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), TransportError>> {
+        let mut this = self.project();
+        while !this.write_buf.is_empty() {
+            let n = ready!(this.writer.as_mut().poll_write(cx, this.write_buf))?;
+            if n == 0 {
+                return Poll::Ready(Err(TransportError::Io(
+                    std::io::ErrorKind::WriteZero.into(),
+                )));
+            }
+            this.write_buf.advance(n);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R, W> Sink<JsonRpcMessage> for ByteTransport<R, W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    type Error = TransportError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Keep at most one message buffered, a slow peer should slow the server down
+        self.poll_write_buf(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: JsonRpcMessage) -> Result<(), Self::Error> {
+        let this = self.project();
+        this.codec.encode(item, this.write_buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_write_buf(cx))?;
+        Poll::Ready(ready!(self.project().writer.poll_flush(cx)).map_err(TransportError::Io))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        Poll::Ready(ready!(self.project().writer.poll_shutdown(cx)).map_err(TransportError::Io))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use tokio::io::ReadBuf;
+
+    /// A reader that hands out one byte at a time and returns `Pending` before each byte,
+    /// the worst case for a reader that loses state between polls
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        pending: bool,
+    }
+
+    impl Trickle {
+        fn new(data: &[u8]) -> Self {
+            Self {
+                data: data.to_vec(),
+                pos: 0,
+                pending: true,
+            }
+        }
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            if self.pos == self.data.len() {
+                return Poll::Ready(Ok(()));
+            }
+            if self.pending {
+                self.pending = false;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.pending = true;
+            buf.put_slice(&[self.data[self.pos]]);
+            self.pos += 1;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn transport(data: &[u8], max_frame_size: usize) -> ByteTransport<Trickle, tokio::io::Sink> {
+        ByteTransport::with_max_frame_size(Trickle::new(data), tokio::io::sink(), max_frame_size)
+    }
+
+    #[tokio::test]
+    async fn test_messages_fed_one_byte_at_a_time() {
+        let mut transport = transport(
+            b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n\
+              {\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\r\n\
+              \n\
+              {\"jsonrpc\":\"2.0\",\"id\":\"last\",\"method\":\"tools/list\"}",
+            DEFAULT_MAX_FRAME_SIZE,
+        );
+
+        match transport.next().await {
+            Some(Ok(JsonRpcMessage::Request(r))) => assert_eq!(r.method, "ping"),
+            other => panic!("Expected Request, got {:?}", other),
+        }
+        match transport.next().await {
+            Some(Ok(JsonRpcMessage::Notification(n))) => {
+                assert_eq!(n.method, "notifications/initialized")
+            }
+            other => panic!("Expected Notification, got {:?}", other),
+        }
+        // The last frame has no trailing newline and is still delivered at EOF
+        match transport.next().await {
+            Some(Ok(JsonRpcMessage::Request(r))) => assert_eq!(r.method, "tools/list"),
+            other => panic!("Expected Request, got {:?}", other),
+        }
+        assert!(transport.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame_is_skipped() {
+        let big = format!(
+            "{{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"{}\"}}\n",
+            "x".repeat(100)
+        );
+        let small = "{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"ping\"}\n";
+        let mut transport = transport(format!("{big}{small}").as_bytes(), 64);
+
+        assert!(matches!(
+            transport.next().await,
+            Some(Err(TransportError::FrameTooLarge(64)))
+        ));
+        match transport.next().await {
+            Some(Ok(JsonRpcMessage::Request(r))) => assert_eq!(r.method, "ping"),
+            other => panic!("Expected Request, got {:?}", other),
+        }
+        assert!(transport.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_frame_does_not_end_stream() {
+        let mut transport = transport(
+            b"not json\n{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n",
+            DEFAULT_MAX_FRAME_SIZE,
+        );
+
+        assert!(matches!(
+            transport.next().await,
+            Some(Err(TransportError::Json(_)))
+        ));
+        assert!(matches!(
+            transport.next().await,
+            Some(Ok(JsonRpcMessage::Request(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_batch_element_is_kept() {
+        let mut transport = transport(
+            b"[{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"},{\"id\":2}]\n",
+            DEFAULT_MAX_FRAME_SIZE,
+        );
+
+        match transport.next().await {
+            Some(Ok(JsonRpcMessage::Batch(messages))) => {
+                assert!(matches!(messages[0], JsonRpcMessage::Request(_)));
+                assert!(matches!(messages[1], JsonRpcMessage::Invalid(_)));
+            }
+            other => panic!("Expected Batch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_encode_writes_one_line() {
+        let mut codec = JsonRpcFrameCodec::default();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                serde_json::from_str(r#"{"jsonrpc":"2.0","method":"ping"}"#).unwrap(),
+                &mut buf,
+            )
+            .unwrap();
+        assert_eq!(&buf[..], b"{\"jsonrpc\":\"2.0\",\"method\":\"ping\"}\n");
+    }
+}
@@ -4,7 +4,7 @@ use anyhow::Result;
 use mcp_client::client::{
     ClientCapabilities, ClientInfo, Error as ClientError, McpClient, McpClientTrait,
 };
-use mcp_client::transport::{StdioTransport, Transport};
+use mcp_client::transport::{StdioTransport, Transport, TransportHandle};
 use mcp_client::McpService;
 use std::collections::HashMap;
 use std::time::Duration;
@@ -34,11 +34,17 @@ async fn main() -> Result<(), ClientError> {
     // Start the transport to get a handle
     let transport_handle = transport.start().await.unwrap();
 
+    // Keep the messages the server sends on its own, e.g. progress updates
+    let server_messages = transport_handle.take_server_messages();
+
     // Create the service with timeout middleware
     let service = McpService::with_timeout(transport_handle, Duration::from_secs(10));
 
     // Create client
     let mut client = McpClient::new(service);
+    if let Some(messages) = server_messages {
+        client = client.with_server_messages(messages);
+    }
 
     // Initialize
     let server_info = client
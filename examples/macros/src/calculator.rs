@@ -27,6 +27,9 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
 #[tool(
     name = "calculator",
     description = "Perform basic arithmetic operations",
+    title = "Calculator",
+    read_only = true,
+    open_world = false,
     params(
         x = "First number in the calculation",
         y = "Second number in the calculation",
@@ -7,7 +7,7 @@ use axum::{
     Router,
 };
 use futures::{stream::Stream, StreamExt, TryStreamExt};
-use mcp_server::{ByteTransport, Server};
+use mcp_server::{ByteTransport, JsonRpcFrameCodec, Server};
 use std::collections::HashMap;
 use tokio_util::codec::FramedRead;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -112,7 +112,7 @@ async fn sse_handler(State(app): State<App>) -> Sse<impl Stream<Item = Result<Ev
     {
         let session = session.clone();
         tokio::spawn(async move {
-            let router = RouterService(counter::CounterRouter::new());
+            let router = RouterService::new(counter::CounterRouter::new());
             let server = Server::new(router);
             let bytes_transport = ByteTransport::new(c2s_read, s2c_write);
             let _result = server
@@ -129,7 +129,7 @@ async fn sse_handler(State(app): State<App>) -> Sse<impl Stream<Item = Result<Ev
             .data(format!("?sessionId={session}")),
     ))
     .chain(
-        FramedRead::new(s2c_read, common::jsonrpc_frame_codec::JsonRpcFrameCodec)
+        FramedRead::new(s2c_read, JsonRpcFrameCodec::default())
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
             .and_then(move |bytes| match std::str::from_utf8(&bytes) {
                 Ok(message) => futures::future::ok(Event::default().event("message").data(message)),
@@ -4,7 +4,7 @@ use mcp_core::{
     handler::{PromptError, ResourceError},
     prompt::{Prompt, PromptArgument},
     protocol::ServerCapabilities,
-    Content, Resource, Tool, ToolError,
+    Content, Resource, Tool, ToolAnnotations, ToolError,
 };
 use mcp_server::router::CapabilitiesBuilder;
 use serde_json::Value;
@@ -44,6 +44,8 @@ impl CounterRouter {
     }
 }
 
+// The list methods spell out the futures the trait returns
+#[allow(clippy::manual_async_fn)]
 impl mcp_server::Router for CounterRouter {
     fn name(&self) -> String {
         "counter".to_string()
@@ -90,7 +92,8 @@ impl mcp_server::Router for CounterRouter {
                         "properties": {},
                         "required": []
                     }),
-                ),
+                )
+                .with_annotations(ToolAnnotations::read_only()),
             ]
         }
     }
@@ -1,2 +1 @@
 pub mod counter;
-pub mod jsonrpc_frame_codec;
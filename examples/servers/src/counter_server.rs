@@ -25,7 +25,7 @@ async fn main() -> Result<()> {
     tracing::info!("Starting MCP server");
 
     // Create an instance of our counter router
-    let router = RouterService(common::counter::CounterRouter::new());
+    let router = RouterService::new(common::counter::CounterRouter::new());
 
     // Create and run the server
     let server = Server::new(router);
@@ -26,7 +26,7 @@ async fn main() -> Result<()> {
     tracing::info!("Starting MCP server");
 
     // Create an instance of our counter router
-    let router = RouterService(CounterRouter::new());
+    let router = RouterService::new(CounterRouter::new());
 
     // Create and run the server
     let server = Server::new(router);